@@ -51,6 +51,18 @@ impl StreamSlice {
         }
     }
 
+    /// Create a StreamSlice from a pointer and length as passed by the C
+    /// side, without checking them. Useful in unit tests.
+    #[cfg(test)]
+    pub unsafe fn from_raw_parts(input: *const u8, input_len: u32, flags: u8, offset: u64) -> Self {
+        Self {
+            input,
+            input_len,
+            flags,
+            offset
+        }
+    }
+
     pub fn is_gap(&self) -> bool {
         self.input.is_null() && self.input_len > 0
     }
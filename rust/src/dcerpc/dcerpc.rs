@@ -18,6 +18,7 @@
 use crate::applayer::{self, *};
 use crate::core::{self, *};
 use crate::dcerpc::parser;
+use lazy_static::lazy_static;
 use nom7::error::{Error, ErrorKind};
 use nom7::number::Endianness;
 use nom7::{Err, IResult, Needed};
@@ -164,7 +165,20 @@ pub fn get_req_type_for_resp(t: u8) -> u8 {
     }
 }
 
+/// A fragment received for a transaction.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DCERPCFragMeta {
+    pub fragnum: u16,
+    pub len: u16,
+    pub flags1: u8,
+    /// position of the PDU among the PDUs of the flow
+    pub arrival_order: u64,
+}
+
 #[derive(Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DCERPCTransaction {
     pub id: u64, // internal transaction ID
     pub ctxid: u16,
@@ -184,11 +198,76 @@ pub struct DCERPCTransaction {
     pub resp_lost: bool,
     pub req_cmd: u8,
     pub resp_cmd: u8,
-    pub activityuuid: Vec<u8>,
+    pub activityuuid: DCERPCUuid,
     pub seqnum: u32,
+    /// state only kept by the UDP parser
+    pub udp: Option<Box<DCERPCUdpTxData>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub tx_data: AppLayerTxData,
 }
 
+/// Transaction state only kept by the UDP parser.
+#[derive(Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DCERPCUdpTxData {
+    pub charset: u8,
+    pub stub_data_truncated_ts: bool,
+    pub stub_data_truncated_tc: bool,
+    pub stub_data_expected_len_ts: u32,
+    pub stub_data_expected_len_tc: u32,
+    /// number of fragments of the stub data PDU, from the highest fragment
+    /// number seen
+    pub stub_frag_total_ts: u16,
+    pub stub_frag_total_tc: u16,
+    /// largest stub length of a single fragment
+    pub stub_frag_len_max_ts: u32,
+    pub stub_frag_len_max_tc: u32,
+    pub stub_sha256_ts: Option<[u8; 32]>,
+    pub stub_sha256_tc: Option<[u8; 32]>,
+    pub stub_ascii_ts: Option<Vec<u8>>,
+    pub stub_ascii_tc: Option<Vec<u8>>,
+    pub stub_strings_ts: Option<Vec<String>>,
+    pub stub_strings_tc: Option<Vec<String>>,
+    pub interfaceuuid: DCERPCUuid,
+    /// object UUID, none when the nil UUID is addressed
+    pub objectuuid: Option<DCERPCUuid>,
+    pub broadcast: bool,
+    pub server_boot: u32,
+    pub pkt_type: u8,
+    pub fragnum: u16,
+    pub flags1: u8,
+    pub ihint: u16,
+    pub ahint: u16,
+    pub if_vers: u32,
+    pub cancelled: bool,
+    pub server_working: bool,
+    /// the client acknowledged the response with an ack PDU
+    pub client_acked: bool,
+    /// fragment numbers acknowledged by fack PDUs, sorted
+    pub acked_frags: Vec<u16>,
+    pub sensitive: bool,
+    pub req_ts: u64,
+    pub resp_ts: u64,
+    pub resp_opnum: u16,
+    pub fault_status: Option<u32>,
+    pub fragment_log: Vec<DCERPCFragMeta>,
+    pub bytes_ts: u64,
+    pub bytes_tc: u64,
+    pub epm_endpoints: Vec<String>,
+    /// raw header of the PDU that started the transaction, serde has no
+    /// support for arrays this long
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub header_bytes: Option<[u8; 80]>,
+    pub complete_notified: bool,
+    pub last_updated: u64,
+    pub auth_type: u8,
+    pub auth_len: u32,
+}
+
+lazy_static! {
+    static ref DCERPC_UDP_TX_DATA_DEFAULT: DCERPCUdpTxData = DCERPCUdpTxData::default();
+}
+
 impl Transaction for DCERPCTransaction {
     fn id(&self) -> u64 {
         // need +1 to match state.tx_id
@@ -203,7 +282,6 @@ impl DCERPCTransaction {
             stub_data_buffer_tc: Vec::new(),
             req_cmd: DCERPC_TYPE_REQUEST,
             resp_cmd: DCERPC_TYPE_RESPONSE,
-            activityuuid: Vec::new(),
             tx_data: AppLayerTxData::new(),
             ..Default::default()
         }
@@ -224,6 +302,23 @@ impl DCERPCTransaction {
     pub fn get_endianness(&self) -> u8 {
         self.endianness
     }
+
+    /// Reassembled stub data in `direction`.
+    pub fn get_stub_data(&self, direction: Direction) -> &[u8] {
+        match direction {
+            Direction::ToServer => &self.stub_data_buffer_ts,
+            Direction::ToClient => &self.stub_data_buffer_tc,
+        }
+    }
+
+    /// State only kept by the UDP parser, the defaults if nothing was kept.
+    pub fn udp(&self) -> &DCERPCUdpTxData {
+        self.udp.as_deref().unwrap_or(&DCERPC_UDP_TX_DATA_DEFAULT)
+    }
+
+    pub fn udp_mut(&mut self) -> &mut DCERPCUdpTxData {
+        self.udp.get_or_insert_with(Default::default)
+    }
 }
 
 #[derive(Debug)]
@@ -233,12 +328,47 @@ pub struct DCERPCRequest {
     pub first_request_seen: u8,
 }
 
+/// A UUID, in the byte order used by the parser: the order of its string
+/// form.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DCERPCUuid(pub [u8; 16]);
+
+impl DCERPCUuid {
+    /// Build a UUID from 16 bytes in the parser's byte order.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        <[u8; 16]>::try_from(bytes).ok().map(Self)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+
+    /// Whether this is the nil UUID.
+    pub fn is_nil(&self) -> bool {
+        self.0.iter().all(|&b| b == 0)
+    }
+}
+
+impl std::fmt::Display for DCERPCUuid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, b) in self.0.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                write!(f, "-")?;
+            }
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DCERPCUuidEntry {
     pub ctxid: u16,
     pub internal_id: u16,
     pub result: u16,
-    pub uuid: Vec<u8>,
+    pub uuid: DCERPCUuid,
     pub version: u16,
     pub versionminor: u16,
     pub flags: u16,
@@ -648,7 +778,7 @@ impl DCERPCState {
         match parser::parse_bindctx_item(input, endianness) {
             Ok((leftover_bytes, ctxitem)) => {
                 let mut uuidentry = DCERPCUuidEntry::new();
-                uuidentry.uuid = ctxitem.uuid;
+                uuidentry.uuid = DCERPCUuid::from_bytes(&ctxitem.uuid).unwrap_or_default();
                 uuidentry.internal_id = uuid_internal_id;
                 uuidentry.ctxid = ctxitem.ctxid;
                 uuidentry.version = ctxitem.version;
@@ -1235,10 +1365,10 @@ pub unsafe extern "C" fn rs_dcerpc_get_tx_cnt(vtx: *mut std::os::raw::c_void) ->
 pub unsafe extern "C" fn rs_dcerpc_get_alstate_progress(tx: *mut std::os::raw::c_void, direction: u8
                                                  )-> std::os::raw::c_int {
     let tx = cast_pointer!(tx, DCERPCTransaction);
-    if direction == Direction::ToServer.into() && tx.req_done {
+    if direction == u8::from(Direction::ToServer) && tx.req_done {
         SCLogDebug!("tx {} TOSERVER progress 1 => {:?}", tx.call_id, tx);
         return 1;
-    } else if direction == Direction::ToClient.into() && tx.resp_done {
+    } else if direction == u8::from(Direction::ToClient) && tx.resp_done {
         SCLogDebug!("tx {} TOCLIENT progress 1 => {:?}", tx.call_id, tx);
         return 1;
     }
@@ -1259,18 +1389,10 @@ pub unsafe extern "C" fn rs_dcerpc_get_tx_data(
 pub unsafe extern "C" fn rs_dcerpc_get_stub_data(
     tx: &mut DCERPCTransaction, buf: *mut *const u8, len: *mut u32, endianness: *mut u8, dir: u8,
 ) {
-    match dir.into() {
-        Direction::ToServer => {
-            *len = tx.stub_data_buffer_ts.len() as u32;
-            *buf = tx.stub_data_buffer_ts.as_ptr();
-            SCLogDebug!("DCERPC Request stub buffer: Setting buffer to: {:?}", *buf);
-        }
-        Direction::ToClient => {
-            *len = tx.stub_data_buffer_tc.len() as u32;
-            *buf = tx.stub_data_buffer_tc.as_ptr();
-            SCLogDebug!("DCERPC Response stub buffer: Setting buffer to: {:?}", *buf);
-        }
-    }
+    let stub_data = tx.get_stub_data(dir.into());
+    *len = stub_data.len() as u32;
+    *buf = stub_data.as_ptr();
+    SCLogDebug!("DCERPC stub buffer: Setting buffer to: {:?}", *buf);
     *endianness = tx.get_endianness();
 }
 
@@ -1408,7 +1530,7 @@ pub unsafe extern "C" fn rs_dcerpc_register_parser() {
 mod tests {
     use crate::applayer::AppLayerResult;
     use crate::core::*;
-    use crate::dcerpc::dcerpc::DCERPCState;
+    use crate::dcerpc::dcerpc::{DCERPCState, DCERPCUuid};
     use std::cmp;
 
     #[test]
@@ -1664,7 +1786,7 @@ mod tests {
             assert_eq!(1, back.accepted_uuid_list.len());
             assert_eq!(
                 vec!(57, 25, 40, 106, 177, 12, 17, 208, 155, 168, 0, 192, 79, 217, 46, 245),
-                back.accepted_uuid_list[0].uuid
+                back.accepted_uuid_list[0].uuid.as_bytes()
             );
             assert_eq!(11, back.accepted_uuid_list[0].internal_id);
         }
@@ -2115,11 +2237,12 @@ mod tests {
             assert_eq!(
                 cmp::Ordering::Equal,
                 bind_uuid
+                    .as_bytes()
                     .iter()
                     .zip(expected_uuid)
                     .map(|(x, y)| x.cmp(y))
                     .find(|&ord| ord != cmp::Ordering::Equal)
-                    .unwrap_or_else(|| bind_uuid.len().cmp(&expected_uuid.len()))
+                    .unwrap_or_else(|| bind_uuid.as_bytes().len().cmp(&expected_uuid.len()))
             );
         }
     }
@@ -2421,7 +2544,7 @@ mod tests {
         if let Some(ref back) = dcerpc_state.bindack {
             assert_eq!(1, back.accepted_uuid_list.len());
             assert_eq!(12, back.accepted_uuid_list[0].ctxid);
-            assert_eq!(expected_uuid1, back.accepted_uuid_list[0].uuid);
+            assert_eq!(expected_uuid1, back.accepted_uuid_list[0].uuid.as_bytes());
         }
         assert_eq!(
             AppLayerResult::ok(),
@@ -2434,7 +2557,7 @@ mod tests {
         if let Some(ref back) = dcerpc_state.bindack {
             assert_eq!(1, back.accepted_uuid_list.len());
             assert_eq!(15, back.accepted_uuid_list[0].ctxid);
-            assert_eq!(expected_uuid2, back.accepted_uuid_list[0].uuid);
+            assert_eq!(expected_uuid2, back.accepted_uuid_list[0].uuid.as_bytes());
         }
         assert_eq!(
             AppLayerResult::ok(),
@@ -2448,7 +2571,7 @@ mod tests {
             assert_eq!(1, back.accepted_uuid_list.len());
             dcerpc_state.data_needed_for_dir = Direction::ToServer;
             assert_eq!(11, back.accepted_uuid_list[0].ctxid);
-            assert_eq!(expected_uuid3, back.accepted_uuid_list[0].uuid);
+            assert_eq!(expected_uuid3, back.accepted_uuid_list[0].uuid.as_bytes());
         }
     }
 
@@ -2504,7 +2627,7 @@ mod tests {
         if let Some(ref back) = dcerpc_state.bindack {
             assert_eq!(1, back.accepted_uuid_list.len());
             assert_eq!(0, back.accepted_uuid_list[0].ctxid);
-            assert_eq!(expected_uuid1, back.accepted_uuid_list[0].uuid);
+            assert_eq!(expected_uuid1, back.accepted_uuid_list[0].uuid.as_bytes());
         }
         assert_eq!(
             AppLayerResult::ok(),
@@ -2517,7 +2640,7 @@ mod tests {
         if let Some(ref back) = dcerpc_state.bindack {
             assert_eq!(1, back.accepted_uuid_list.len());
             assert_eq!(1, back.accepted_uuid_list[0].ctxid);
-            assert_eq!(expected_uuid2, back.accepted_uuid_list[0].uuid);
+            assert_eq!(expected_uuid2, back.accepted_uuid_list[0].uuid.as_bytes());
         }
     }
 
@@ -2545,4 +2668,29 @@ mod tests {
         assert_eq!(0, tx.ctxid);
         assert_eq!(14, tx.stub_data_buffer_ts.len());
     }
+
+    #[test]
+    fn test_uuid() {
+        let bytes = [
+            0xe1, 0xaf, 0x83, 0x08, 0x5d, 0x1f, 0x11, 0xc9, 0x91, 0xa4, 0x08, 0x00, 0x2b, 0x14,
+            0xa0, 0xfa,
+        ];
+        let uuid = DCERPCUuid::from_bytes(&bytes).unwrap();
+        assert_eq!("e1af8308-5d1f-11c9-91a4-08002b14a0fa", uuid.to_string());
+        assert_eq!(
+            uuid::Uuid::from_bytes(bytes).to_hyphenated().to_string(),
+            uuid.to_string()
+        );
+        assert_eq!("00000000-0000-0000-0000-000000000000", DCERPCUuid::default().to_string());
+        assert!(DCERPCUuid::default().is_nil());
+        assert!(!uuid.is_nil());
+
+        assert_eq!(DCERPCUuid(bytes), uuid);
+        let mut other = bytes;
+        other[15] = 0xfb;
+        assert_ne!(DCERPCUuid(other), uuid);
+
+        assert_eq!(None, DCERPCUuid::from_bytes(&bytes[..15]));
+        assert_eq!(None, DCERPCUuid::from_bytes(&[0; 17]));
+    }
 }
@@ -18,19 +18,233 @@
 use crate::applayer::{self, *};
 use crate::core::{self, Direction, DIR_BOTH};
 use crate::dcerpc::dcerpc::{
-    DCERPCTransaction, DCERPC_MAX_TX, DCERPC_TYPE_REQUEST, DCERPC_TYPE_RESPONSE, PFCL1_FRAG, PFCL1_LASTFRAG,
+    DCERPCFragMeta, DCERPCTransaction, DCERPCUuid, DCERPC_MAX_TX, DCERPC_TYPE_ACK,
+    DCERPC_TYPE_CANCEL_ACK, DCERPC_TYPE_CL_CANCEL, DCERPC_TYPE_FACK, DCERPC_TYPE_FAULT,
+    DCERPC_TYPE_NOCALL, DCERPC_TYPE_PING, DCERPC_TYPE_REJECT,
+    DCERPC_TYPE_REQUEST, DCERPC_TYPE_RESPONSE, DCERPC_TYPE_WORKING, PFCL1_BROADCAST, PFCL1_FRAG,
+    PFCL1_IDEMPOTENT, PFCL1_LASTFRAG, PFCL2_RESERVED_04, PFCL2_RESERVED_08, PFCL2_RESERVED_10,
+    PFCL2_RESERVED_20, PFCL2_RESERVED_40, PFCL2_RESERVED_80, dcerpc_type_string,
     rs_dcerpc_get_alstate_progress, ALPROTO_DCERPC, PARSER_NAME,
 };
-use nom7::Err;
+use nom7::number::Endianness;
+use nom7::{Err, Needed};
+use sha2::{Digest, Sha256};
 use std;
 use std::ffi::CString;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::RwLock;
+use crate::dcerpc::interfaces::{lookup_interface, resolve_operation};
 use crate::dcerpc::parser;
+use crate::conf::{conf_get, conf_get_bool, get_memval};
+use uuid::Uuid;
 
 // Constant DCERPC UDP Header length
 pub const DCERPC_UDP_HDR_LEN: i32 = 80;
 
+/// Minimum body length of a fragment that is not the last one. `fraglen`
+/// doesn't include the header, so header only PDUs have a `fraglen` of 0,
+/// but the body of every fragment but the last is a multiple of 8 bytes.
+pub const DCERPC_UDP_MIN_FRAGLEN: u16 = 8;
+
+#[derive(AppLayerEvent, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DCERPCUDPEvent {
+    /// Transaction exceeded the maximum number of fragments
+    TooManyFragments,
+    /// Reassembled stub data exceeded the configured maximum size
+    StubTooLarge,
+    /// Reserved or contradictory header flags
+    SuspiciousFlags,
+    /// Response without a matching request
+    ResponseWithoutRequest,
+    /// Serial number changed between fragments of a request
+    SerialMismatchDuringReassembly,
+    /// Reassembled stub data does not match the fragment lengths
+    ReassemblyLengthMismatch,
+    /// Opnum out of range for the interface
+    SuspiciousOpnum,
+    /// Integer byte order not allowed by the config
+    UnexpectedByteOrder,
+    /// Call cancelled by a cancel PDU
+    CallCancelled,
+    /// Request for an interface on the configured sensitive list
+    SensitiveInterface,
+    /// Fragment other than the last one with a body shorter than
+    /// `DCERPC_UDP_MIN_FRAGLEN`
+    FraglenTooSmall,
+    /// Response opnum differs from the opnum of its request
+    OpnumMismatch,
+    /// Call failed with a fault PDU
+    RpcFault,
+    /// Packet type not defined by the protocol, only raised in strict mode
+    UnknownPktType,
+    /// Fragment number not greater than the one of the previous fragment
+    NonMonotonicFragnum,
+    /// Many unanswered requests for the same activity
+    PossibleRpcScan,
+    /// Server doesn't know the call
+    NoCall,
+    /// Transaction limit reached with no completed transaction to evict
+    TooManyTransactions,
+    /// Server boot time changed during an activity
+    ServerBootChanged,
+    /// Unfragmented PDU with a non-zero fragment number
+    ContradictoryFragFlags,
+    /// Control PDU with more body than its type defines
+    UnexpectedStubData,
+    /// Stub data buffered on the flow reached the configured budget
+    FlowStubBudgetExceeded,
+    /// Datagram larger than the configured threshold
+    LargeDatagram,
+    /// Serial number of an activity reused with another interface UUID
+    SerialInterfaceConflict,
+}
+
+/// Default for the maximum number of fragments reassembled per direction of
+/// a transaction.
+pub const DCERPC_UDP_MAX_FRAGS: u16 = 256;
+
+/// Default for the maximum size of the reassembled stub data per direction
+/// of a transaction.
+pub const DCERPC_UDP_STUB_DATA_MAX: u32 = 1024 * 1024;
+
+/// Default for the highest expected opnum on interfaces that are not
+/// well-known.
+pub const DCERPC_UDP_MAX_OPNUM: u16 = 512;
+
+/// Default for the maximum number of transactions kept per flow.
+pub const DCERPC_UDP_MAX_TX: usize = 1024;
+
+/// Default for the number of unanswered requests of an activity at which
+/// `PossibleRpcScan` is set.
+pub const DCERPC_UDP_SCAN_THRESHOLD: u32 = 16;
+
+/// Default for the size of a datagram above which `LargeDatagram` is set.
+pub const DCERPC_UDP_LARGE_DATAGRAM_THRESHOLD: u32 = 4096;
+
+/// Largest input accepted by the parser: a UDP payload can't be longer.
+pub const DCERPC_UDP_MAX_INPUT_LEN: u32 = 65535;
+
+/// Bounds of the UTF-16LE strings extracted from the stub data: shortest
+/// string in characters, most strings per direction and longest string in
+/// characters, longer strings are truncated.
+pub const DCERPC_UDP_STUB_STRING_MIN_LEN: usize = 4;
+pub const DCERPC_UDP_STUB_STRINGS_MAX: usize = 16;
+pub const DCERPC_UDP_STUB_STRING_MAX_LEN: usize = 256;
+
+/// Most distinct interface UUIDs remembered per flow.
+pub const DCERPC_UDP_MAX_INTERFACES: usize = 256;
+
+/// Most distinct activity UUIDs remembered per flow, both for the activity
+/// count and for the server boot times.
+pub const DCERPC_UDP_MAX_ACTIVITIES: usize = 4096;
+
+/// Most (activity, serial number) pairs whose interface UUID is remembered
+/// per flow.
+pub const DCERPC_UDP_MAX_SERIALS: usize = 4096;
+
+/// Number of stub data bytes included in the EVE log.
+pub const DCERPC_UDP_STUB_PREVIEW_LEN: usize = 64;
+
+/// Integer byte orders of the data representation label, used as a mask of
+/// the byte orders allowed on a flow.
+pub const DCERPC_UDP_BYTE_ORDER_BIG: u8 = 0x01;
+pub const DCERPC_UDP_BYTE_ORDER_LITTLE: u8 = 0x02;
+pub const DCERPC_UDP_BYTE_ORDER_ANY: u8 = DCERPC_UDP_BYTE_ORDER_BIG | DCERPC_UDP_BYTE_ORDER_LITTLE;
+
+static mut DCERPC_UDP_MAX_FRAGS_CFG: u16 = DCERPC_UDP_MAX_FRAGS;
+static mut DCERPC_UDP_STUB_DATA_MAX_CFG: u32 = DCERPC_UDP_STUB_DATA_MAX;
+static mut DCERPC_UDP_MAX_OPNUM_CFG: u16 = DCERPC_UDP_MAX_OPNUM;
+static mut DCERPC_UDP_ALLOWED_BYTE_ORDERS_CFG: u8 = DCERPC_UDP_BYTE_ORDER_ANY;
+static mut DCERPC_UDP_STRIP_STUB_PADDING_CFG: bool = false;
+static mut DCERPC_UDP_FRAGMENT_LOG_CFG: bool = false;
+static mut DCERPC_UDP_STRICT_CFG: bool = false;
+static mut DCERPC_UDP_SCAN_THRESHOLD_CFG: u32 = DCERPC_UDP_SCAN_THRESHOLD;
+static mut DCERPC_UDP_LARGE_DATAGRAM_THRESHOLD_CFG: u32 = DCERPC_UDP_LARGE_DATAGRAM_THRESHOLD;
+static mut DCERPC_UDP_MAX_TX_CFG: usize = DCERPC_UDP_MAX_TX;
+static mut DCERPC_UDP_REORDER_TOLERANCE_CFG: bool = false;
+static mut DCERPC_UDP_METADATA_ONLY_CFG: bool = false;
+static mut DCERPC_UDP_FLOW_STUB_BUDGET_CFG: u64 = 0;
+static DCERPC_UDP_SENSITIVE_INTERFACES_CFG: RwLock<Vec<DCERPCUuid>> = RwLock::new(Vec::new());
+
+/// Which sides of a call a transaction has seen.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversationState {
+    RequestOnly = 0,
+    ResponseOnly = 1,
+    Complete = 2,
+}
+
+/// Connectionless (UDP) PDU types.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DCERPCPktType {
+    Request = DCERPC_TYPE_REQUEST,
+    Ping = DCERPC_TYPE_PING,
+    Response = DCERPC_TYPE_RESPONSE,
+    Fault = DCERPC_TYPE_FAULT,
+    Working = DCERPC_TYPE_WORKING,
+    Nocall = DCERPC_TYPE_NOCALL,
+    Reject = DCERPC_TYPE_REJECT,
+    Ack = DCERPC_TYPE_ACK,
+    Cancel = DCERPC_TYPE_CL_CANCEL,
+    Fack = DCERPC_TYPE_FACK,
+    CancelAck = DCERPC_TYPE_CANCEL_ACK,
+}
+
+impl DCERPCPktType {
+    pub fn from_u8(t: u8) -> Option<Self> {
+        match t {
+            DCERPC_TYPE_REQUEST => Some(Self::Request),
+            DCERPC_TYPE_PING => Some(Self::Ping),
+            DCERPC_TYPE_RESPONSE => Some(Self::Response),
+            DCERPC_TYPE_FAULT => Some(Self::Fault),
+            DCERPC_TYPE_WORKING => Some(Self::Working),
+            DCERPC_TYPE_NOCALL => Some(Self::Nocall),
+            DCERPC_TYPE_REJECT => Some(Self::Reject),
+            DCERPC_TYPE_ACK => Some(Self::Ack),
+            DCERPC_TYPE_CL_CANCEL => Some(Self::Cancel),
+            DCERPC_TYPE_FACK => Some(Self::Fack),
+            DCERPC_TYPE_CANCEL_ACK => Some(Self::CancelAck),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Request => "request",
+            Self::Ping => "ping",
+            Self::Response => "response",
+            Self::Fault => "fault",
+            Self::Working => "working",
+            Self::Nocall => "nocall",
+            Self::Reject => "reject",
+            Self::Ack => "ack",
+            Self::Cancel => "cancel",
+            Self::Fack => "fack",
+            Self::CancelAck => "cancel_ack",
+        }
+    }
+}
+
+/// Name of the authentication protocol `auth_proto` of a PDU.
+pub fn auth_proto_name(auth_proto: u8) -> Option<&'static str> {
+    match auth_proto {
+        0x00 => Some("none"),
+        0x01 => Some("dce_private"),
+        0x02 => Some("dce_public"),
+        0x09 => Some("spnego"),
+        0x0a => Some("ntlmssp"),
+        0x0e => Some("schannel"),
+        0x10 => Some("kerberos"),
+        0x44 => Some("netlogon"),
+        0xff => Some("default"),
+        _ => None,
+    }
+}
+
 #[derive(Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DCERPCHdrUdp {
     pub rpc_vers: u8,
     pub pkt_type: u8,
@@ -38,9 +252,9 @@ pub struct DCERPCHdrUdp {
     pub flags2: u8,
     pub drep: Vec<u8>,
     pub serial_hi: u8,
-    pub objectuuid: Vec<u8>,
-    pub interfaceuuid: Vec<u8>,
-    pub activityuuid: Vec<u8>,
+    pub objectuuid: DCERPCUuid,
+    pub interfaceuuid: DCERPCUuid,
+    pub activityuuid: DCERPCUuid,
     pub server_boot: u32,
     pub if_vers: u32,
     pub seqnum: u32,
@@ -53,12 +267,303 @@ pub struct DCERPCHdrUdp {
     pub serial_lo: u8,
 }
 
+/// Counts that prefix an NDR conformant array. The offset and actual count
+/// are only present for conformant varying arrays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NdrPrefix {
+    pub max_count: u32,
+    pub offset: Option<u32>,
+    pub actual_count: Option<u32>,
+}
+
+/// Parse the NDR array counts at the start of a stub.
+pub fn parse_ndr_prefix(stub: &[u8], endianness: Endianness) -> Option<NdrPrefix> {
+    parser::parse_ndr_prefix(stub, endianness).ok().map(|(_, prefix)| prefix)
+}
+
+/// Errors found in a parsed connectionless header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderError {
+    /// The data representation label is missing or short
+    MissingDrep,
+    /// The RPC version is not 4
+    BadVersion,
+    /// The header is short, more bytes are needed
+    Incomplete(usize),
+    /// The header could not be parsed or has invalid fields
+    Malformed,
+}
+
+impl DCERPCHdrUdp {
+    /// Get the packet type, `None` if it is not a known connectionless type.
+    pub fn get_hdr_pkt_type(&self) -> Option<DCERPCPktType> {
+        DCERPCPktType::from_u8(self.pkt_type)
+    }
+
+    /// Serial number of the PDU, from `serial_hi` and `serial_lo`.
+    pub fn get_serial(&self) -> u16 {
+        (self.serial_hi as u16) << 8 | self.serial_lo as u16
+    }
+
+    /// First byte of the data representation (integer and character
+    /// formats).
+    pub fn get_hdr_drep_0(&self) -> Result<u8, HeaderError> {
+        self.drep.first().copied().ok_or(HeaderError::MissingDrep)
+    }
+
+    /// Second byte of the data representation (floating point format).
+    pub fn get_hdr_drep_1(&self) -> Result<u8, HeaderError> {
+        self.drep.get(1).copied().ok_or(HeaderError::MissingDrep)
+    }
+
+    /// Serialize the header to JSON, for dumping parser output.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+}
+
+/// Parser counters of a flow.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DCERPCUDPStats {
+    /// parse calls
+    pub datagrams: u64,
+    /// PDU headers parsed and validated
+    pub headers_ok: u64,
+    /// PDU headers that failed to parse or validate
+    pub headers_err: u64,
+    /// PDUs that could not be reassembled or were reassembled with data
+    /// missing
+    pub reassembly_failures: u64,
+    /// events set on transactions
+    pub events_raised: u64,
+}
+
+/// What was done with a datagram.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct UdpParseOutcome {
+    /// bytes of the input consumed, including bytes buffered until the rest
+    /// of a partial PDU arrives
+    pub consumed: usize,
+    /// bytes still needed to complete a partial PDU
+    pub needed: usize,
+    /// events raised while parsing the datagram
+    pub events: Vec<DCERPCUDPEvent>,
+    /// all PDUs of the datagram were parsed
+    pub complete: bool,
+    /// the datagram was rejected
+    pub error: bool,
+}
+
+impl UdpParseOutcome {
+    fn ok(consumed: usize) -> Self {
+        Self { consumed, complete: true, ..Default::default() }
+    }
+
+    fn incomplete(consumed: usize, needed: usize) -> Self {
+        Self { consumed, needed, ..Default::default() }
+    }
+
+    fn err() -> Self {
+        Self { error: true, ..Default::default() }
+    }
+}
+
+impl From<UdpParseOutcome> for AppLayerResult {
+    fn from(outcome: UdpParseOutcome) -> Self {
+        if outcome.error {
+            AppLayerResult::err()
+        } else if !outcome.complete {
+            AppLayerResult::incomplete(outcome.consumed as u32, outcome.needed as u32)
+        } else {
+            AppLayerResult::ok()
+        }
+    }
+}
+
+/// Function called with a transaction and `user_data` once both the request
+/// and the response of the transaction are done.
+#[derive(Debug, Clone, Copy)]
+pub struct DCERPCUDPTxCompleteCallback {
+    pub cb: extern "C" fn(*mut DCERPCTransaction, *mut std::os::raw::c_void),
+    pub user_data: *mut std::os::raw::c_void,
+}
+
+/// Completion and fragment counts of a transaction.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TxStatus {
+    pub req_done: bool,
+    pub resp_done: bool,
+    pub frag_cnt_ts: u32,
+    pub frag_cnt_tc: u32,
+}
+
+/// C representation of `TxStatus`.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DCERPCUDPTxStatus {
+    pub req_done: u8,
+    pub resp_done: u8,
+    pub frag_cnt_ts: u32,
+    pub frag_cnt_tc: u32,
+}
+
+impl From<TxStatus> for DCERPCUDPTxStatus {
+    fn from(status: TxStatus) -> Self {
+        Self {
+            req_done: status.req_done as u8,
+            resp_done: status.resp_done as u8,
+            frag_cnt_ts: status.frag_cnt_ts,
+            frag_cnt_tc: status.frag_cnt_tc,
+        }
+    }
+}
+
+/// Owned copy of the data of a transaction, independent of the state.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TxSnapshot {
+    pub id: u64,
+    pub seqnum: u32,
+    pub opnum: u16,
+    pub pkt_type: u8,
+    pub activityuuid: DCERPCUuid,
+    pub interfaceuuid: DCERPCUuid,
+    pub if_vers: u32,
+    pub req_done: bool,
+    pub resp_done: bool,
+    pub req_lost: bool,
+    pub resp_lost: bool,
+    pub frag_cnt_ts: u16,
+    pub frag_cnt_tc: u16,
+    pub fault_status: Option<u32>,
+    pub auth_type: u8,
+    pub stub_data_ts: Vec<u8>,
+    pub stub_data_tc: Vec<u8>,
+}
+
+/// C representation of `TxSnapshot`. The stub data is not copied, only its
+/// length. UUIDs are in the byte order used by the parser.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DCERPCUDPTxSnapshot {
+    pub id: u64,
+    pub seqnum: u32,
+    pub opnum: u16,
+    pub pkt_type: u8,
+    pub activityuuid: [u8; 16],
+    pub interfaceuuid: [u8; 16],
+    pub if_vers: u32,
+    pub req_done: u8,
+    pub resp_done: u8,
+    pub req_lost: u8,
+    pub resp_lost: u8,
+    pub frag_cnt_ts: u16,
+    pub frag_cnt_tc: u16,
+    pub has_fault_status: u8,
+    pub fault_status: u32,
+    pub auth_type: u8,
+    pub stub_data_len_ts: u32,
+    pub stub_data_len_tc: u32,
+}
+
+impl From<&TxSnapshot> for DCERPCUDPTxSnapshot {
+    fn from(snapshot: &TxSnapshot) -> Self {
+        Self {
+            id: snapshot.id,
+            seqnum: snapshot.seqnum,
+            opnum: snapshot.opnum,
+            pkt_type: snapshot.pkt_type,
+            activityuuid: snapshot.activityuuid.0,
+            interfaceuuid: snapshot.interfaceuuid.0,
+            if_vers: snapshot.if_vers,
+            req_done: snapshot.req_done as u8,
+            resp_done: snapshot.resp_done as u8,
+            req_lost: snapshot.req_lost as u8,
+            resp_lost: snapshot.resp_lost as u8,
+            frag_cnt_ts: snapshot.frag_cnt_ts,
+            frag_cnt_tc: snapshot.frag_cnt_tc,
+            has_fault_status: snapshot.fault_status.is_some() as u8,
+            fault_status: snapshot.fault_status.unwrap_or(0),
+            auth_type: snapshot.auth_type,
+            stub_data_len_ts: snapshot.stub_data_ts.len() as u32,
+            stub_data_len_tc: snapshot.stub_data_tc.len() as u32,
+        }
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct DCERPCUDPState {
     state_data: AppLayerStateData,
     pub tx_id: u64,
     pub transactions: VecDeque<DCERPCTransaction>,
     tx_index_completed: usize,
+    /// partial PDU carried over from a previous call
+    pub buffer: Vec<u8>,
+    /// number of events raised on this flow
+    pub events: u16,
+    /// max fragments per direction of a transaction, from the config
+    pub max_frags: u16,
+    /// max stub data size per direction of a transaction, from the config
+    pub stub_data_max: u32,
+    /// max stub data buffered by all the transactions of the flow, 0 for no
+    /// limit, from the config
+    pub flow_stub_budget: u64,
+    /// stub data buffered by all the transactions of the flow
+    pub stub_bytes: u64,
+    /// highest expected opnum on unknown interfaces, from the config
+    pub max_opnum: u16,
+    /// mask of the allowed integer byte orders, from the config
+    pub allowed_byte_orders: u8,
+    /// exclude alignment padding of the last fragment from the stub data,
+    /// from the config
+    pub strip_stub_padding: bool,
+    /// record the fragments of each transaction, from the config
+    pub fragment_log: bool,
+    /// raise events on packet types the protocol doesn't define, from the
+    /// config
+    pub strict: bool,
+    /// unanswered requests of an activity that make it look like a scan, 0
+    /// to disable, from the config
+    pub scan_threshold: u32,
+    /// size of a datagram above which it is unusually large, 0 to disable,
+    /// from the config
+    pub large_datagram_threshold: u32,
+    /// max transactions kept on the flow, from the config
+    pub max_transactions: usize,
+    /// expect responses to be seen before their request, from the config
+    pub reorder_tolerance: bool,
+    /// only record the headers of requests and responses, without
+    /// reassembling the stub data, from the config
+    pub metadata_only: bool,
+    /// the transaction limit was hit and `TooManyTransactions` set
+    tx_limit_reached: bool,
+    /// interface UUIDs whose requests are tagged, from the config
+    pub sensitive_interfaces: Vec<DCERPCUuid>,
+    /// logical clock, advanced on every parse call
+    pub tick: u64,
+    /// time of the current datagram in microseconds, from the flow
+    pub ts: u64,
+    pub stats: DCERPCUDPStats,
+    /// distinct interface UUIDs requested on the flow, in order of first
+    /// use, at most `DCERPC_UDP_MAX_INTERFACES`
+    pub interface_uuids: Vec<DCERPCUuid>,
+    /// distinct activity UUIDs seen on the flow, at most
+    /// `DCERPC_UDP_MAX_ACTIVITIES`
+    pub activity_uuids: HashSet<DCERPCUuid>,
+    /// events raised while parsing the current datagram
+    datagram_events: Vec<DCERPCUDPEvent>,
+    /// raw header of the PDU being handled
+    raw_header: Option<[u8; DCERPC_UDP_HDR_LEN as usize]>,
+    /// length of the auth verifier trailing the PDU being handled
+    auth_len: u32,
+    /// last server boot time seen per activity UUID, for at most
+    /// `DCERPC_UDP_MAX_ACTIVITIES` activities
+    server_boots: HashMap<DCERPCUuid, u32>,
+    /// interface UUID of the calls per activity UUID and serial number
+    serial_interfaces: HashMap<(DCERPCUuid, u16), DCERPCUuid>,
+    /// called when a transaction completes
+    pub tx_complete_cb: Option<DCERPCUDPTxCompleteCallback>,
 }
 
 impl State<DCERPCTransaction> for DCERPCUDPState {
@@ -73,15 +578,104 @@ impl State<DCERPCTransaction> for DCERPCUDPState {
 
 impl DCERPCUDPState {
     pub fn new() -> Self {
-        Default::default()
+        return Self {
+            max_frags: unsafe { DCERPC_UDP_MAX_FRAGS_CFG },
+            stub_data_max: unsafe { DCERPC_UDP_STUB_DATA_MAX_CFG },
+            flow_stub_budget: unsafe { DCERPC_UDP_FLOW_STUB_BUDGET_CFG },
+            max_opnum: unsafe { DCERPC_UDP_MAX_OPNUM_CFG },
+            allowed_byte_orders: unsafe { DCERPC_UDP_ALLOWED_BYTE_ORDERS_CFG },
+            strip_stub_padding: unsafe { DCERPC_UDP_STRIP_STUB_PADDING_CFG },
+            fragment_log: unsafe { DCERPC_UDP_FRAGMENT_LOG_CFG },
+            strict: unsafe { DCERPC_UDP_STRICT_CFG },
+            scan_threshold: unsafe { DCERPC_UDP_SCAN_THRESHOLD_CFG },
+            large_datagram_threshold: unsafe { DCERPC_UDP_LARGE_DATAGRAM_THRESHOLD_CFG },
+            max_transactions: unsafe { DCERPC_UDP_MAX_TX_CFG },
+            reorder_tolerance: unsafe { DCERPC_UDP_REORDER_TOLERANCE_CFG },
+            metadata_only: unsafe { DCERPC_UDP_METADATA_ONLY_CFG },
+            sensitive_interfaces: DCERPC_UDP_SENSITIVE_INTERFACES_CFG
+                .read()
+                .map(|cfg| cfg.clone())
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+    }
+
+    /// Clear the state so it can be reused for a new flow. Allocated
+    /// capacity is kept and the configured limits are preserved. Detection
+    /// state lives in the transactions' `tx_data` and is owned by the
+    /// engine, it is released together with the transactions.
+    pub fn reset(&mut self) {
+        self.state_data = AppLayerStateData::new();
+        self.tx_id = 0;
+        self.transactions.clear();
+        self.tx_index_completed = 0;
+        self.buffer.clear();
+        self.events = 0;
+        self.tick = 0;
+        self.ts = 0;
+        self.stats = DCERPCUDPStats::default();
+        self.interface_uuids.clear();
+        self.activity_uuids.clear();
+        self.raw_header = None;
+        self.auth_len = 0;
+        self.stub_bytes = 0;
+        self.tx_limit_reached = false;
+        self.server_boots.clear();
+        self.serial_interfaces.clear();
+        self.tx_complete_cb = None;
+    }
+
+    /// Whether a PDU is being reassembled: part of a PDU is buffered, or a
+    /// transaction has not seen the last fragment of its request or response.
+    pub fn has_pending(&self) -> bool {
+        !self.buffer.is_empty()
+            || self.transactions.iter().any(|tx| {
+                (tx.frag_cnt_ts > 0 && !tx.req_done) || (tx.frag_cnt_tc > 0 && !tx.resp_done)
+            })
+    }
+
+    /// Drop the transactions that have not been updated in the last
+    /// `max_age` parse calls. Returns the number of transactions dropped.
+    pub fn evict_stale(&mut self, max_age: u64) -> usize {
+        let len = self.transactions.len();
+        let tick = self.tick;
+        let mut released = 0;
+        self.transactions.retain(|tx| {
+            let keep = tick.saturating_sub(tx.udp().last_updated) <= max_age;
+            if !keep {
+                released += tx.buffered_stub_len();
+            }
+            keep
+        });
+        self.stub_bytes = self.stub_bytes.saturating_sub(released);
+        let evicted = len - self.transactions.len();
+        if evicted > 0 {
+            SCLogDebug!("evicted {} stale transactions", evicted);
+            self.tx_index_completed = 0;
+        }
+        evicted
     }
 
     fn create_tx(&mut self,  hdr: &DCERPCHdrUdp) -> DCERPCTransaction {
         let mut tx = DCERPCTransaction::new();
         tx.id = self.tx_id;
-        tx.endianness = hdr.drep[0] & 0x10;
-        tx.activityuuid = hdr.activityuuid.to_vec();
+        if let Ok(drep) = hdr.get_hdr_drep_0() {
+            tx.endianness = drep & 0x10;
+            tx.udp_mut().charset = drep & 0x0f;
+        }
+        tx.activityuuid = hdr.activityuuid;
+        tx.udp_mut().interfaceuuid = hdr.interfaceuuid;
+        // the nil object UUID means no object is addressed
+        if !hdr.objectuuid.is_nil() {
+            tx.udp_mut().objectuuid = Some(hdr.objectuuid);
+        }
+        tx.opnum = hdr.opnum;
+        tx.udp_mut().ihint = hdr.ihint;
+        tx.udp_mut().ahint = hdr.ahint;
+        tx.udp_mut().if_vers = hdr.if_vers;
         tx.seqnum = hdr.seqnum;
+        tx.udp_mut().broadcast = hdr.flags1 & PFCL1_BROADCAST != 0;
+        tx.udp_mut().header_bytes = self.raw_header;
         self.tx_id += 1;
         if self.transactions.len() > unsafe { DCERPC_MAX_TX } {
             let mut index = self.tx_index_completed;
@@ -98,6 +692,23 @@ impl DCERPCUDPState {
         tx
     }
 
+    /// Set an event on the transaction at `index`.
+    fn set_tx_event(&mut self, index: usize, event: DCERPCUDPEvent) {
+        if let Some(tx) = self.transactions.get_mut(index) {
+            tx.tx_data.set_event(event as u8);
+            self.events = self.events.saturating_add(1);
+            self.stats.events_raised += 1;
+            self.datagram_events.push(event);
+        }
+    }
+
+    /// Set an event. The event is set on the most recent transaction.
+    fn set_event(&mut self, event: DCERPCUDPEvent) {
+        if !self.transactions.is_empty() {
+            self.set_tx_event(self.transactions.len() - 1, event);
+        }
+    }
+
     pub fn free_tx(&mut self, tx_id: u64) {
         SCLogDebug!("Freeing TX with ID {} TX.ID {}", tx_id, tx_id+1);
         let len = self.transactions.len();
@@ -116,7 +727,15 @@ impl DCERPCUDPState {
             SCLogDebug!("freeing TX with ID {} TX.ID {} at index {} left: {} max id: {}",
                             tx_id, tx_id+1, index, self.transactions.len(), self.tx_id);
             self.tx_index_completed = 0;
-            self.transactions.remove(index);
+            self.release_tx(index);
+        }
+    }
+
+    /// Remove the transaction at `index`, releasing its stub data from the
+    /// flow budget.
+    fn release_tx(&mut self, index: usize) {
+        if let Some(tx) = self.transactions.remove(index) {
+            self.stub_bytes = self.stub_bytes.saturating_sub(tx.buffered_stub_len());
         }
     }
 
@@ -140,305 +759,4929 @@ impl DCERPCUDPState {
         None
     }
 
-    fn find_incomplete_tx(&mut self, hdr: &DCERPCHdrUdp) -> Option<&mut DCERPCTransaction> {
-        for tx in &mut self.transactions {
-            if tx.seqnum == hdr.seqnum && tx.activityuuid == hdr.activityuuid && ((hdr.pkt_type == DCERPC_TYPE_REQUEST && !tx.req_done) || (hdr.pkt_type == DCERPC_TYPE_RESPONSE && !tx.resp_done)) {
-                SCLogDebug!("found tx id {}, last tx_id {}, {} {}", tx.id, self.tx_id, tx.seqnum, tx.activityuuid[0]);
-                return Some(tx);
+    /// Borrow the reassembled stub data in `direction` of the transaction
+    /// with internal id `tx_id`.
+    pub fn stub_slice(&self, tx_id: u64, direction: Direction) -> Option<&[u8]> {
+        self.transactions
+            .iter()
+            .find(|tx| tx.id == tx_id)
+            .map(|tx| tx.get_stub_data(direction))
+    }
+
+    /// Rebuild the PDU of the transaction with internal id `tx_id` in
+    /// `direction` as a single unfragmented PDU: a header from the fields
+    /// stored on the transaction followed by the reassembled stub data.
+    /// Header fields that are not stored, like `flags2` and the floating point
+    /// format, are set to 0. `None` if there is no PDU in that direction or the stub
+    /// doesn't fit in one PDU.
+    pub fn reconstruct_pdu(&self, tx_id: u64, direction: Direction) -> Option<Vec<u8>> {
+        let tx = self.transactions.iter().find(|tx| tx.id == tx_id)?;
+        let (pkt_type, frag_cnt, opnum) = match direction {
+            Direction::ToServer => (DCERPC_TYPE_REQUEST, tx.frag_cnt_ts, tx.opnum),
+            Direction::ToClient => (DCERPC_TYPE_RESPONSE, tx.frag_cnt_tc, tx.udp().resp_opnum),
+        };
+        let stub_data = tx.get_stub_data(direction);
+        if frag_cnt == 0 || stub_data.len() > u16::MAX as usize {
+            return None;
+        }
+        let le = tx.endianness & 0x10 != 0;
+        let u16b = |v: u16| if le { v.to_le_bytes() } else { v.to_be_bytes() };
+        let u32b = |v: u32| if le { v.to_le_bytes() } else { v.to_be_bytes() };
+        let mut pdu = Vec::with_capacity(DCERPC_UDP_HDR_LEN as usize + stub_data.len());
+        let flags1 = tx.udp().flags1 & !(PFCL1_FRAG | PFCL1_LASTFRAG);
+        pdu.extend_from_slice(&[4, pkt_type, flags1, 0]);
+        pdu.extend_from_slice(&[tx.endianness | tx.udp().charset, 0, 0]);
+        pdu.push((tx.call_id >> 8) as u8);
+        for uuid in [tx.udp().objectuuid.unwrap_or_default(), tx.udp().interfaceuuid, tx.activityuuid] {
+            pdu.extend_from_slice(&uuid_to_wire(&uuid));
+        }
+        pdu.extend_from_slice(&u32b(tx.udp().server_boot));
+        pdu.extend_from_slice(&u32b(tx.udp().if_vers));
+        pdu.extend_from_slice(&u32b(tx.seqnum));
+        pdu.extend_from_slice(&u16b(opnum));
+        pdu.extend_from_slice(&u16b(tx.udp().ihint));
+        pdu.extend_from_slice(&u16b(tx.udp().ahint));
+        pdu.extend_from_slice(&u16b(stub_data.len() as u16));
+        pdu.extend_from_slice(&u16b(0));
+        pdu.extend_from_slice(&[tx.udp().auth_type, tx.call_id as u8]);
+        pdu.extend_from_slice(stub_data);
+        Some(pdu)
+    }
+
+    /// Group the transactions of the flow by interface UUID.
+    pub fn transactions_by_interface(&self) -> HashMap<[u8; 16], Vec<&DCERPCTransaction>> {
+        let mut groups: HashMap<[u8; 16], Vec<&DCERPCTransaction>> = HashMap::new();
+        for tx in &self.transactions {
+            groups.entry(tx.udp().interfaceuuid.0).or_default().push(tx);
+        }
+        groups
+    }
+
+    /// Get the request/response completion and fragment counts of the
+    /// transaction with internal id `tx_id`.
+    pub fn tx_status(&self, tx_id: u64) -> Option<TxStatus> {
+        self.transactions.iter().find(|tx| tx.id == tx_id).map(|tx| TxStatus {
+            req_done: tx.req_done,
+            resp_done: tx.resp_done,
+            frag_cnt_ts: tx.frag_cnt_ts as u32,
+            frag_cnt_tc: tx.frag_cnt_tc as u32,
+        })
+    }
+
+    /// Find the transaction of the call the PDU belongs to, if that call
+    /// completed in the direction of the PDU. Calls closed after losing data
+    /// are not considered.
+    fn find_tx(&self, hdr: &DCERPCHdrUdp) -> Option<usize> {
+        let pkt_type = hdr.get_hdr_pkt_type();
+        self.transactions.iter().position(|tx| {
+            tx.seqnum == hdr.seqnum && tx.activityuuid == hdr.activityuuid
+                && ((pkt_type == Some(DCERPCPktType::Request) && tx.req_done && !tx.req_lost)
+                    || (pkt_type == Some(DCERPCPktType::Response) && tx.resp_done && !tx.resp_lost
+                        && tx.is_response_from(hdr)))
+        })
+    }
+
+    fn find_incomplete_tx(&self, hdr: &DCERPCHdrUdp) -> Option<usize> {
+        let pkt_type = hdr.get_hdr_pkt_type();
+        self.transactions.iter().position(|tx| {
+            tx.seqnum == hdr.seqnum && tx.activityuuid == hdr.activityuuid && ((pkt_type == Some(DCERPCPktType::Request) && !tx.req_done) || (pkt_type == Some(DCERPCPktType::Response) && !tx.resp_done && tx.is_response_from(hdr)))
+        })
+    }
+
+    /// Whether the PDU belongs to a broadcast call seen on this flow.
+    fn is_broadcast_call(&self, hdr: &DCERPCHdrUdp) -> bool {
+        self.transactions.iter().any(|tx| {
+            tx.udp().broadcast && tx.seqnum == hdr.seqnum && tx.activityuuid == hdr.activityuuid
+        })
+    }
+
+    /// Record the header of a request or response PDU on the transaction of
+    /// its call, without reassembling the stub data or running the checks
+    /// of `handle_fragment_data`. Used in metadata only mode, other PDUs are
+    /// ignored.
+    fn handle_metadata(&mut self, hdr: &DCERPCHdrUdp) {
+        let pkt_type = match hdr.get_hdr_pkt_type() {
+            Some(t @ (DCERPCPktType::Request | DCERPCPktType::Response)) => t,
+            _ => return,
+        };
+        let index = match self.find_incomplete_tx(hdr) {
+            Some(index) => index,
+            None => {
+                if self.find_tx(hdr).is_some() || !self.make_room() {
+                    return;
+                }
+                let tx = self.create_tx(hdr);
+                self.transactions.push_back(tx);
+                self.transactions.len() - 1
             }
+        };
+        let done = (hdr.flags1 & PFCL1_FRAG) == 0 || (hdr.flags1 & PFCL1_LASTFRAG) != 0;
+        let tx = &mut self.transactions[index];
+        tx.udp_mut().last_updated = self.tick;
+        tx.udp_mut().pkt_type = hdr.pkt_type;
+        tx.udp_mut().fragnum = hdr.fragnum;
+        tx.udp_mut().flags1 = hdr.flags1;
+        if pkt_type == DCERPCPktType::Request {
+            tx.opnum = hdr.opnum;
+            tx.frag_cnt_ts = tx.frag_cnt_ts.saturating_add(1);
+            tx.req_done |= done;
+        } else {
+            tx.udp_mut().server_boot = hdr.server_boot;
+            tx.frag_cnt_tc = tx.frag_cnt_tc.saturating_add(1);
+            tx.resp_done |= done;
         }
-        None
+        SCLogDebug!("metadata of {}", tx.summary());
     }
 
     pub fn handle_fragment_data(&mut self, hdr: &DCERPCHdrUdp, input: &[u8]) -> bool {
-        if hdr.pkt_type != DCERPC_TYPE_REQUEST && hdr.pkt_type != DCERPC_TYPE_RESPONSE {
-            SCLogDebug!("Unrecognized packet type");
+        self.check_control_body(hdr, input);
+        let pkt_type = match hdr.get_hdr_pkt_type() {
+            Some(t @ (DCERPCPktType::Request | DCERPCPktType::Response | DCERPCPktType::Fault)) => t,
+            Some(DCERPCPktType::Cancel | DCERPCPktType::CancelAck) => {
+                self.handle_cancel(hdr);
+                return true;
+            }
+            Some(DCERPCPktType::Fack) => {
+                self.handle_fack(hdr, input);
+                return true;
+            }
+            Some(DCERPCPktType::Ack) => {
+                self.handle_ack(hdr);
+                return true;
+            }
+            Some(t @ (DCERPCPktType::Working | DCERPCPktType::Nocall)) => {
+                self.handle_server_status(hdr, t);
+                return true;
+            }
+            Some(_) => {
+                SCLogDebug!("Unhandled packet type {}", hdr.pkt_type);
+                return false;
+            }
+            None => {
+                SCLogDebug!("Unrecognized packet type {}", hdr.pkt_type);
+                if !self.strict {
+                    return false;
+                }
+                // In strict mode the PDU is accepted, on a transaction of its
+                // own that carries the event
+                if self.make_room() {
+                    let mut tx = self.create_tx(hdr);
+                    tx.req_done = true;
+                    tx.resp_done = true;
+                    self.transactions.push_back(tx);
+                    self.set_event(DCERPCUDPEvent::UnknownPktType);
+                }
+                return true;
+            }
+        };
+
+        if let Err(_err) = hdr.get_hdr_drep_0() {
+            SCLogDebug!("rejecting PDU: {:?}", _err);
             return false;
         }
-
-        let mut otx = self.find_incomplete_tx(hdr);
-        if otx.is_none() {
-            let ntx = self.create_tx(hdr);
-            SCLogDebug!("new tx id {}, last tx_id {}, {} {}", ntx.id, self.tx_id, ntx.seqnum, ntx.activityuuid[0]);
-            self.transactions.push_back(ntx);
-            otx = self.transactions.back_mut();
+        if pkt_type == DCERPCPktType::Fault {
+            self.handle_fault(hdr, input);
+            return true;
         }
 
-        if let Some(tx) = otx {
-            let done = (hdr.flags1 & PFCL1_FRAG) == 0 || (hdr.flags1 & PFCL1_LASTFRAG) != 0;
-
-            match hdr.pkt_type {
-                DCERPC_TYPE_REQUEST => {
-                    tx.stub_data_buffer_ts.extend_from_slice(input);
-                    tx.frag_cnt_ts += 1;
-                    if done {
-                        tx.req_done = true;
-                    }
+        let index = match self.find_incomplete_tx(hdr) {
+            Some(index) => {
+                SCLogDebug!("found {}, last tx_id {}", self.transactions[index].summary(), self.tx_id);
+                index
+            }
+            None => {
+                // The PDU of a call that is already complete in this
+                // direction is a retransmission, don't create a duplicate
+                if let Some(index) = self.find_tx(hdr) {
+                    SCLogDebug!("retransmission for {}", self.transactions[index].summary());
+                    self.transactions[index].udp_mut().last_updated = self.tick;
                     return true;
                 }
-                DCERPC_TYPE_RESPONSE => {
-                    tx.stub_data_buffer_tc.extend_from_slice(input);
-                    tx.frag_cnt_tc += 1;
-                    if done {
-                        tx.resp_done = true;
-                    }
+                if !self.make_room() {
                     return true;
                 }
-                _ => {
-                    // unreachable
+                // Each server answering a broadcast request gets its own
+                // response transaction
+                let broadcast_response = pkt_type == DCERPCPktType::Response && self.is_broadcast_call(hdr);
+                let mut ntx = self.create_tx(hdr);
+                if broadcast_response {
+                    ntx.udp_mut().broadcast = true;
+                    ntx.req_done = true;
+                }
+                SCLogDebug!("new {}, last tx_id {}", ntx.summary(), self.tx_id);
+                self.transactions.push_back(ntx);
+                let index = self.transactions.len() - 1;
+                // With reordering tolerated the request may still come,
+                // the event is set at the end of the flow if it doesn't
+                if pkt_type == DCERPCPktType::Response && !broadcast_response
+                    && !self.reorder_tolerance
+                {
+                    self.set_tx_event(index, DCERPCUDPEvent::ResponseWithoutRequest);
                 }
+                if pkt_type == DCERPCPktType::Request {
+                    self.check_rpc_scan(index, hdr);
+                }
+                index
             }
-        }
-        return false; // unreachable
-    }
+        };
 
-    pub fn handle_input_data(&mut self, input: &[u8]) -> AppLayerResult {
-        // Input length should at least be header length
-        if (input.len() as i32) < DCERPC_UDP_HDR_LEN {
-            return AppLayerResult::err();
+        if self.activity_uuids.len() < DCERPC_UDP_MAX_ACTIVITIES {
+            self.activity_uuids.insert(hdr.activityuuid);
+        }
+        self.validate_flags(index, hdr);
+        self.check_byte_order(index, hdr);
+        self.check_fraglen(index, hdr);
+        self.check_fragnum(index, hdr);
+        self.check_frag_flags(index, hdr);
+        self.check_server_boot(index, hdr);
+        if pkt_type == DCERPCPktType::Request {
+            if self.transactions[index].frag_cnt_ts == 0 {
+                if self.transactions[index].frag_cnt_tc > 0 {
+                    // request of a call whose response came first, the
+                    // transaction was created from the response header
+                    SCLogDebug!("request after its response for tx id {}", self.transactions[index].id);
+                    self.transactions[index].opnum = hdr.opnum;
+                }
+                self.check_opnum(index, hdr);
+                self.check_sensitive_interface(index, hdr);
+                self.check_serial_interface(index, hdr);
+                if self.interface_uuids.len() < DCERPC_UDP_MAX_INTERFACES
+                    && !self.interface_uuids.contains(&hdr.interfaceuuid)
+                {
+                    self.interface_uuids.push(hdr.interfaceuuid);
+                }
+            }
+            self.check_serial(index, hdr);
+        } else if self.transactions[index].frag_cnt_tc == 0 {
+            self.check_response_opnum(index, hdr);
         }
 
-        // Call header parser first
-        match parser::parse_dcerpc_udp_header(input) {
-            Ok((leftover_bytes, header)) => {
-                if header.rpc_vers != 4 {
-                    SCLogDebug!("DCERPC UDP Header did not validate.");
-                    return AppLayerResult::err();
+        let max_frags = self.max_frags;
+        let stub_data_max = self.stub_data_max as usize;
+        let done = (hdr.flags1 & PFCL1_FRAG) == 0 || (hdr.flags1 & PFCL1_LASTFRAG) != 0;
+        let padding = if done && self.strip_stub_padding { stub_padding_len(input) } else { 0 };
+        let input = &input[..input.len() - padding];
+        let stub_len = (hdr.fraglen as u32).saturating_sub(padding as u32);
+        let ts = self.ts;
+        let fragment_log = self.fragment_log;
+        let arrival_order = self.stats.headers_ok;
+        let auth_len = self.auth_len;
+        let budget_left = match self.flow_stub_budget {
+            0 => usize::MAX,
+            budget => budget.saturating_sub(self.stub_bytes) as usize,
+        };
+        let tx = &mut self.transactions[index];
+        let stub_before = tx.buffered_stub_len();
+        if hdr.auth_proto != 0 {
+            tx.udp_mut().auth_type = hdr.auth_proto;
+            tx.udp_mut().auth_len = auth_len;
+        }
+        if fragment_log {
+            tx.udp_mut().fragment_log.push(DCERPCFragMeta {
+                fragnum: hdr.fragnum,
+                len: hdr.fraglen,
+                flags1: hdr.flags1,
+                arrival_order,
+            });
+        }
+        tx.udp_mut().last_updated = self.tick;
+        tx.udp_mut().pkt_type = hdr.pkt_type;
+        tx.udp_mut().fragnum = hdr.fragnum;
+        tx.udp_mut().flags1 = hdr.flags1;
+        let first_frag = hdr.fragnum == 0;
+        let (stub_data_buffer, frag_cnt, truncated) = match pkt_type {
+            DCERPCPktType::Request => {
+                if done {
+                    tx.req_done = true;
                 }
-                if leftover_bytes.len() < header.fraglen as usize {
-                    SCLogDebug!("Insufficient data: leftover_bytes {}, fraglen {}", leftover_bytes.len(), header.fraglen);
-                    return AppLayerResult::err();
+                if tx.frag_cnt_ts == 0 {
+                    tx.udp_mut().req_ts = ts;
                 }
-                if !self.handle_fragment_data(&header, &leftover_bytes[..header.fraglen as usize]) {
-                    return AppLayerResult::err();
+                tx.udp_mut().bytes_ts += input.len() as u64;
+                if first_frag && !tx.stub_data_buffer_ts.is_empty() {
+                    SCLogDebug!("first fragment of a new request PDU, resetting stub data");
+                    tx.stub_data_buffer_ts.clear();
+                    tx.udp_mut().stub_data_expected_len_ts = 0;
+                    tx.udp_mut().stub_frag_total_ts = 0;
+                    tx.udp_mut().stub_frag_len_max_ts = 0;
+                    tx.udp_mut().stub_data_truncated_ts = false;
                 }
+                tx.udp_mut().stub_sha256_ts = None;
+                tx.udp_mut().stub_ascii_ts = None;
+                tx.udp_mut().stub_strings_ts = None;
+                tx.udp_mut().stub_data_expected_len_ts = tx.udp().stub_data_expected_len_ts.saturating_add(stub_len);
+                tx.udp_mut().stub_frag_total_ts = std::cmp::max(tx.udp().stub_frag_total_ts, hdr.fragnum.saturating_add(1));
+                tx.udp_mut().stub_frag_len_max_ts = std::cmp::max(tx.udp().stub_frag_len_max_ts, stub_len);
+                (
+                    &mut tx.stub_data_buffer_ts,
+                    &mut tx.frag_cnt_ts,
+                    &mut tx.udp.get_or_insert_with(Default::default).stub_data_truncated_ts,
+                )
             }
-            Err(Err::Incomplete(_)) => {
-                // Insufficient data.
-                SCLogDebug!("Insufficient data while parsing DCERPC request");
-                return AppLayerResult::err();
+            _ => {
+                if done {
+                    tx.resp_done = true;
+                }
+                if tx.frag_cnt_tc == 0 {
+                    tx.udp_mut().resp_ts = ts;
+                }
+                tx.udp_mut().bytes_tc += input.len() as u64;
+                if first_frag && !tx.stub_data_buffer_tc.is_empty() {
+                    SCLogDebug!("first fragment of a new response PDU, resetting stub data");
+                    tx.stub_data_buffer_tc.clear();
+                    tx.udp_mut().stub_data_expected_len_tc = 0;
+                    tx.udp_mut().stub_frag_total_tc = 0;
+                    tx.udp_mut().stub_frag_len_max_tc = 0;
+                    tx.udp_mut().stub_data_truncated_tc = false;
+                }
+                tx.udp_mut().server_boot = hdr.server_boot;
+                tx.udp_mut().stub_sha256_tc = None;
+                tx.udp_mut().stub_ascii_tc = None;
+                tx.udp_mut().stub_strings_tc = None;
+                tx.udp_mut().stub_data_expected_len_tc = tx.udp().stub_data_expected_len_tc.saturating_add(stub_len);
+                tx.udp_mut().stub_frag_total_tc = std::cmp::max(tx.udp().stub_frag_total_tc, hdr.fragnum.saturating_add(1));
+                tx.udp_mut().stub_frag_len_max_tc = std::cmp::max(tx.udp().stub_frag_len_max_tc, stub_len);
+                (
+                    &mut tx.stub_data_buffer_tc,
+                    &mut tx.frag_cnt_tc,
+                    &mut tx.udp.get_or_insert_with(Default::default).stub_data_truncated_tc,
+                )
             }
-            Err(_) => {
-                // Error, probably malformed data.
-                SCLogDebug!("An error occurred while parsing DCERPC request");
-                return AppLayerResult::err();
+        };
+        *frag_cnt = frag_cnt.saturating_add(1);
+        let mut event = None;
+        if *frag_cnt <= max_frags {
+            let max = std::cmp::min(stub_data_max, stub_data_buffer.len().saturating_add(budget_left));
+            if !*truncated && !append_stub_data(stub_data_buffer, hdr, input, max) {
+                *truncated = true;
+                if max < stub_data_max {
+                    SCLogDebug!("flow stub budget exhausted by tx id {}", tx.id);
+                    event = Some(DCERPCUDPEvent::FlowStubBudgetExceeded);
+                } else {
+                    SCLogDebug!("stub data exceeds {} bytes for tx id {}", stub_data_max, tx.id);
+                    event = Some(DCERPCUDPEvent::StubTooLarge);
+                }
             }
+        } else if Some(*frag_cnt) == max_frags.checked_add(1) {
+            SCLogDebug!("too many fragments ({}) for tx id {}", max_frags, tx.id);
+            event = Some(DCERPCUDPEvent::TooManyFragments);
         }
-        return AppLayerResult::ok();
+        let stub_after = tx.buffered_stub_len();
+        self.stub_bytes = (self.stub_bytes + stub_after).saturating_sub(stub_before);
+        if let Some(event) = event {
+            self.set_tx_event(index, event);
+        }
+        if done {
+            self.check_reassembly_len(index, pkt_type);
+            if pkt_type == DCERPCPktType::Response {
+                self.transactions[index].parse_epm_endpoints();
+            }
+        }
+        #[cfg(debug_assertions)]
+        self.debug_check_stub_len(index, pkt_type, hdr, input);
+        SCLogDebug!("updated {}", self.transactions[index].summary());
+        true
     }
-}
 
-#[no_mangle]
-pub unsafe extern "C" fn rs_dcerpc_udp_parse(
-    _flow: *const core::Flow, state: *mut std::os::raw::c_void, _pstate: *mut std::os::raw::c_void,
-    stream_slice: StreamSlice,
-    _data: *const std::os::raw::c_void,
-) -> AppLayerResult {
-    let state = cast_pointer!(state, DCERPCUDPState);
-    if !stream_slice.is_gap() {
-        return state.handle_input_data(stream_slice.as_slice());
+    /// Verify the stub length accounting of a transaction after a fragment
+    /// went into it: the fragment is bounded by its header, and the stub
+    /// buffer never holds more than the fragments announced or than the
+    /// configured maximum.
+    #[cfg(debug_assertions)]
+    fn debug_check_stub_len(
+        &self, index: usize, pkt_type: DCERPCPktType, hdr: &DCERPCHdrUdp, input: &[u8],
+    ) {
+        let tx = &self.transactions[index];
+        let (expected, len) = match pkt_type {
+            DCERPCPktType::Request => (tx.udp().stub_data_expected_len_ts, tx.stub_data_buffer_ts.len()),
+            _ => (tx.udp().stub_data_expected_len_tc, tx.stub_data_buffer_tc.len()),
+        };
+        debug_assert!(input.len() <= hdr.fraglen as usize,
+                      "fragment of {} bytes, fraglen {}", input.len(), hdr.fraglen);
+        debug_assert!(len <= expected as usize,
+                      "stub of {} bytes, fragments announced {}", len, expected);
+        debug_assert!(len <= self.stub_data_max as usize,
+                      "stub of {} bytes, max {}", len, self.stub_data_max);
     }
-    AppLayerResult::err()
-}
 
-#[no_mangle]
-pub extern "C" fn rs_dcerpc_udp_state_free(state: *mut std::os::raw::c_void) {
-    std::mem::drop(unsafe { Box::from_raw(state as *mut DCERPCUDPState) });
-}
+    /// Handle a fault PDU. The call it refers to is closed with the status
+    /// from the body and `RpcFault` is set. A fault for a call that is not
+    /// known gets a transaction of its own.
+    fn handle_fault(&mut self, hdr: &DCERPCHdrUdp, input: &[u8]) {
+        let is_call = |tx: &DCERPCTransaction| {
+            tx.seqnum == hdr.seqnum && tx.activityuuid == hdr.activityuuid
+        };
+        if let Some(tx) = self.transactions.iter_mut().find(|tx| {
+            is_call(tx) && tx.resp_cmd == DCERPC_TYPE_FAULT
+        }) {
+            SCLogDebug!("retransmitted fault for {}", tx.summary());
+            tx.udp_mut().last_updated = self.tick;
+            return;
+        }
+        let index = match self.transactions.iter().position(|tx| is_call(tx) && !tx.resp_done) {
+            Some(index) => index,
+            None => {
+                if !self.make_room() {
+                    return;
+                }
+                let mut tx = self.create_tx(hdr);
+                tx.req_done = true;
+                self.transactions.push_back(tx);
+                self.transactions.len() - 1
+            }
+        };
+        let endianness = match hdr.get_hdr_drep_0() {
+            Ok(drep) if drep & 0x10 != 0 => Endianness::Little,
+            _ => Endianness::Big,
+        };
+        let tx = &mut self.transactions[index];
+        tx.udp_mut().last_updated = self.tick;
+        tx.udp_mut().pkt_type = hdr.pkt_type;
+        tx.resp_cmd = DCERPC_TYPE_FAULT;
+        tx.resp_done = true;
+        tx.udp_mut().fault_status = parser::parse_fault_status(input, endianness)
+            .ok()
+            .map(|(_, status)| status);
+        SCLogDebug!("fault {:?} for {}", tx.udp().fault_status, tx.summary());
+        self.set_tx_event(index, DCERPCUDPEvent::RpcFault);
+    }
 
-#[no_mangle]
-pub extern "C" fn rs_dcerpc_udp_state_new(_orig_state: *mut std::os::raw::c_void, _orig_proto: core::AppProto) -> *mut std::os::raw::c_void {
-    let state = DCERPCUDPState::new();
-    let boxed = Box::new(state);
-    return Box::into_raw(boxed) as *mut _;
-}
+    /// Handle a cancel or cancel ack PDU. The call it refers to is closed
+    /// and marked cancelled. PDUs for calls that are not known are ignored.
+    fn handle_cancel(&mut self, hdr: &DCERPCHdrUdp) {
+        let index = match self.transactions.iter().position(|tx| {
+            tx.seqnum == hdr.seqnum && tx.activityuuid == hdr.activityuuid
+        }) {
+            Some(index) => index,
+            None => {
+                SCLogDebug!("cancel for unknown call, seqnum {}", hdr.seqnum);
+                return;
+            }
+        };
+        let tx = &mut self.transactions[index];
+        tx.udp_mut().last_updated = self.tick;
+        if tx.udp().cancelled {
+            return;
+        }
+        SCLogDebug!("cancelled {}", tx.summary());
+        tx.udp_mut().cancelled = true;
+        tx.req_done = true;
+        // without a response the call is logged as unreplied
+        if !tx.resp_done {
+            tx.resp_lost = tx.frag_cnt_tc == 0;
+            tx.resp_done = true;
+        }
+        self.set_tx_event(index, DCERPCUDPEvent::CallCancelled);
+    }
 
-#[no_mangle]
-pub unsafe extern "C" fn rs_dcerpc_udp_state_transaction_free(
-    state: *mut std::os::raw::c_void, tx_id: u64,
-) {
-    let dce_state = cast_pointer!(state, DCERPCUDPState);
-    SCLogDebug!("freeing tx {}", tx_id);
-    dce_state.free_tx(tx_id);
-}
+    /// Handle a working or nocall PDU sent by the server in reply to a ping.
+    /// Working marks the call as being processed, nocall sets `NoCall`.
+    /// Neither carries stub data. PDUs for calls that are not known are
+    /// ignored.
+    fn handle_server_status(&mut self, hdr: &DCERPCHdrUdp, pkt_type: DCERPCPktType) {
+        let index = match self.transactions.iter().position(|tx| {
+            tx.seqnum == hdr.seqnum && tx.activityuuid == hdr.activityuuid
+        }) {
+            Some(index) => index,
+            None => {
+                SCLogDebug!("{} for unknown call, seqnum {}", pkt_type.as_str(), hdr.seqnum);
+                return;
+            }
+        };
+        let tx = &mut self.transactions[index];
+        tx.udp_mut().last_updated = self.tick;
+        SCLogDebug!("{} for {}", pkt_type.as_str(), tx.summary());
+        if pkt_type == DCERPCPktType::Working {
+            tx.udp_mut().server_working = true;
+        } else {
+            self.set_tx_event(index, DCERPCUDPEvent::NoCall);
+        }
+    }
 
-#[no_mangle]
-pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_data(
-    tx: *mut std::os::raw::c_void)
-    -> *mut AppLayerTxData
-{
-    let tx = cast_pointer!(tx, DCERPCTransaction);
-    return &mut tx.tx_data;
-}
+    /// Handle an ack PDU, sent by the client once it received the whole
+    /// response. The call is closed in both directions and is the first to
+    /// go when room is needed for new transactions. PDUs for calls that are
+    /// not known are ignored.
+    fn handle_ack(&mut self, hdr: &DCERPCHdrUdp) {
+        let tx = match self.transactions.iter_mut().find(|tx| {
+            tx.seqnum == hdr.seqnum && tx.activityuuid == hdr.activityuuid
+        }) {
+            Some(tx) => tx,
+            None => {
+                SCLogDebug!("ack for unknown call, seqnum {}", hdr.seqnum);
+                return;
+            }
+        };
+        tx.udp_mut().last_updated = self.tick;
+        tx.udp_mut().client_acked = true;
+        tx.req_done = true;
+        tx.resp_done = true;
+        SCLogDebug!("ack for {}", tx.summary());
+    }
 
-#[no_mangle]
-pub unsafe extern "C" fn rs_dcerpc_udp_get_tx(
-    state: *mut std::os::raw::c_void, tx_id: u64,
-) -> *mut std::os::raw::c_void {
-    let dce_state = cast_pointer!(state, DCERPCUDPState);
-    match dce_state.get_tx(tx_id) {
-        Some(tx) => {
-            return tx as *const _ as *mut _;
-        },
-        None => {
-            return std::ptr::null_mut();
+    /// Handle a fack PDU. The fragment number of the header is the last
+    /// fragment received in order, bit `n` of the selective acks in the body
+    /// stands for fragment `fragnum + 1 + n`. At most `max_frags` fragment
+    /// numbers are recorded per transaction. PDUs for calls that are not
+    /// known are ignored.
+    fn handle_fack(&mut self, hdr: &DCERPCHdrUdp, input: &[u8]) {
+        let max_frags = self.max_frags as usize;
+        let tx = match self.transactions.iter_mut().find(|tx| {
+            tx.seqnum == hdr.seqnum && tx.activityuuid == hdr.activityuuid
+        }) {
+            Some(tx) => tx,
+            None => {
+                SCLogDebug!("fack for unknown call, seqnum {}", hdr.seqnum);
+                return;
+            }
+        };
+        tx.udp_mut().last_updated = self.tick;
+        let endianness = match hdr.get_hdr_drep_0() {
+            Ok(drep) if drep & 0x10 != 0 => Endianness::Little,
+            _ => Endianness::Big,
+        };
+        let selack = parser::parse_fack_body(input, endianness)
+            .map(|(_, (_, selack))| selack)
+            .unwrap_or_default();
+        let mut acked: Vec<u16> = (0..=hdr.fragnum).take(max_frags).collect();
+        for (word, mask) in selack.iter().enumerate() {
+            for bit in 0..32 {
+                if mask & (1 << bit) == 0 {
+                    continue;
+                }
+                let fragnum = hdr.fragnum as usize + 1 + word * 32 + bit;
+                if fragnum > u16::MAX as usize || acked.len() >= max_frags {
+                    break;
+                }
+                acked.push(fragnum as u16);
+            }
         }
-    } 
-}
+        tx.udp_mut().acked_frags.extend(acked);
+        tx.udp_mut().acked_frags.sort_unstable();
+        tx.udp_mut().acked_frags.dedup();
+        tx.udp_mut().acked_frags.truncate(max_frags);
+        SCLogDebug!("fack through fragment {} for {}", hdr.fragnum, tx.summary());
+    }
 
-#[no_mangle]
-pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_cnt(vtx: *mut std::os::raw::c_void) -> u64 {
-    let dce_state = cast_pointer!(vtx, DCERPCUDPState);
-    dce_state.tx_id
-}
+    /// Once a PDU is complete, compare the reassembled stub data with the
+    /// sum of the fragment lengths and set `ReassemblyLengthMismatch` if
+    /// data went missing.
+    fn check_reassembly_len(&mut self, index: usize, pkt_type: DCERPCPktType) {
+        let tx = &self.transactions[index];
+        let (expected, len) = match pkt_type {
+            DCERPCPktType::Request => (tx.udp().stub_data_expected_len_ts, tx.stub_data_buffer_ts.len()),
+            _ => (tx.udp().stub_data_expected_len_tc, tx.stub_data_buffer_tc.len()),
+        };
+        if expected as usize != len {
+            SCLogDebug!("reassembled {} bytes, expected {} for tx id {}", len, expected, tx.id);
+            self.stats.reassembly_failures += 1;
+            self.set_tx_event(index, DCERPCUDPEvent::ReassemblyLengthMismatch);
+        }
+    }
+
+    /// Set `SuspiciousOpnum` if the requested opnum is past the last
+    /// operation of a well-known interface, or above the configured maximum
+    /// for other interfaces.
+    fn check_opnum(&mut self, index: usize, hdr: &DCERPCHdrUdp) {
+        let suspicious = match lookup_interface(hdr.interfaceuuid.as_bytes()) {
+            Some(iface) => hdr.opnum as usize >= iface.operations.len(),
+            None => hdr.opnum > self.max_opnum,
+        };
+        if suspicious {
+            SCLogDebug!("suspicious opnum {} for tx id {}", hdr.opnum, self.transactions[index].id);
+            self.set_tx_event(index, DCERPCUDPEvent::SuspiciousOpnum);
+        }
+    }
+
+    /// Record the opnum of the first response fragment and set
+    /// `OpnumMismatch` if it differs from the opnum of the request.
+    fn check_response_opnum(&mut self, index: usize, hdr: &DCERPCHdrUdp) {
+        let tx = &mut self.transactions[index];
+        tx.udp_mut().resp_opnum = hdr.opnum;
+        if tx.frag_cnt_ts > 0 && tx.opnum != hdr.opnum {
+            SCLogDebug!("response opnum {} for request opnum {} of tx id {}", hdr.opnum,
+                        tx.opnum, tx.id);
+            self.set_tx_event(index, DCERPCUDPEvent::OpnumMismatch);
+        }
+    }
+
+    /// Tag the transaction and set `SensitiveInterface` if the requested
+    /// interface is on the configured list.
+    fn check_sensitive_interface(&mut self, index: usize, hdr: &DCERPCHdrUdp) {
+        if self.sensitive_interfaces.contains(&hdr.interfaceuuid) {
+            SCLogDebug!("sensitive interface for tx id {}", self.transactions[index].id);
+            self.transactions[index].udp_mut().sensitive = true;
+            self.set_tx_event(index, DCERPCUDPEvent::SensitiveInterface);
+        }
+    }
+
+    /// Record the serial number of the first request fragment as the call
+    /// id of the transaction, and set `SerialMismatchDuringReassembly` if a
+    /// later fragment of the same request carries a different one.
+    fn check_serial(&mut self, index: usize, hdr: &DCERPCHdrUdp) {
+        let serial = hdr.get_serial() as u32;
+        let tx = &mut self.transactions[index];
+        if tx.frag_cnt_ts == 0 {
+            tx.call_id = serial;
+        } else if tx.call_id != serial {
+            SCLogDebug!("serial {} does not match call id {} of tx id {}", serial, tx.call_id, tx.id);
+            self.set_tx_event(index, DCERPCUDPEvent::SerialMismatchDuringReassembly);
+        }
+    }
+
+    /// Check the header flags for bits reserved for future use and for
+    /// combinations that a conforming implementation does not send. The
+    /// PDU is still processed, but `SuspiciousFlags` is set on the
+    /// transaction at `index`.
+    fn validate_flags(&mut self, index: usize, hdr: &DCERPCHdrUdp) {
+        let reserved2 = PFCL2_RESERVED_04 | PFCL2_RESERVED_08 | PFCL2_RESERVED_10
+            | PFCL2_RESERVED_20 | PFCL2_RESERVED_40 | PFCL2_RESERVED_80;
+        let valid = if hdr.flags2 & reserved2 != 0 {
+            SCLogDebug!("reserved flags2 bits set: {:#x}", hdr.flags2);
+            false
+        } else if hdr.flags1 & PFCL1_BROADCAST != 0 && hdr.flags1 & PFCL1_IDEMPOTENT == 0 {
+            // broadcast calls always have idempotent semantics
+            SCLogDebug!("broadcast without idempotent: {:#x}", hdr.flags1);
+            false
+        } else {
+            true
+        };
+        if !valid {
+            self.set_tx_event(index, DCERPCUDPEvent::SuspiciousFlags);
+        }
+    }
+
+    /// Make room for a new transaction once `max_transactions` is reached by
+    /// evicting the oldest transaction acknowledged by the client, or else
+    /// the oldest completed transaction. If none is complete
+    /// `TooManyTransactions` is set, once, and false is returned: the PDU
+    /// is dropped instead of creating a transaction.
+    fn make_room(&mut self) -> bool {
+        if self.transactions.len() < self.max_transactions {
+            self.tx_limit_reached = false;
+            return true;
+        }
+        let index = self
+            .transactions
+            .iter()
+            .position(|tx| tx.udp().client_acked)
+            .or_else(|| self.transactions.iter().position(|tx| tx.req_done && tx.resp_done));
+        if let Some(index) = index {
+            SCLogDebug!("transaction limit reached, evicting {}", self.transactions[index].summary());
+            self.release_tx(index);
+            self.tx_index_completed = 0;
+            self.tx_limit_reached = false;
+            return true;
+        }
+        SCLogDebug!("transaction limit of {} reached", self.max_transactions);
+        if !self.tx_limit_reached {
+            self.tx_limit_reached = true;
+            self.set_event(DCERPCUDPEvent::TooManyTransactions);
+        }
+        false
+    }
+
+    /// Set `PossibleRpcScan` on the request that brings the number of
+    /// unanswered requests of its activity to the scan threshold. The event
+    /// is set once, more requests for the activity don't repeat it.
+    fn check_rpc_scan(&mut self, index: usize, hdr: &DCERPCHdrUdp) {
+        if self.scan_threshold == 0 {
+            return;
+        }
+        let unanswered = self
+            .transactions
+            .iter()
+            .filter(|tx| {
+                tx.activityuuid == hdr.activityuuid && tx.frag_cnt_tc == 0
+                    && tx.resp_cmd != DCERPC_TYPE_FAULT
+            })
+            .count();
+        if unanswered == self.scan_threshold as usize {
+            SCLogDebug!("{} unanswered requests for the activity of tx id {}", unanswered,
+                        self.transactions[index].id);
+            self.set_tx_event(index, DCERPCUDPEvent::PossibleRpcScan);
+        }
+    }
+
+    /// Set `ServerBootChanged` if the server boot time of the PDU differs
+    /// from the last one seen for its activity. A boot time of 0 is the
+    /// client not knowing it yet. Broadcast calls are answered by several
+    /// servers and are not checked, nor are new activities once
+    /// `DCERPC_UDP_MAX_ACTIVITIES` are known.
+    fn check_server_boot(&mut self, index: usize, hdr: &DCERPCHdrUdp) {
+        if hdr.server_boot == 0 || self.transactions[index].udp().broadcast {
+            return;
+        }
+        if self.server_boots.len() >= DCERPC_UDP_MAX_ACTIVITIES
+            && !self.server_boots.contains_key(&hdr.activityuuid)
+        {
+            return;
+        }
+        match self.server_boots.insert(hdr.activityuuid, hdr.server_boot) {
+            Some(server_boot) if server_boot != hdr.server_boot => {
+                SCLogDebug!("server boot changed from {} to {} for tx id {}", server_boot,
+                            hdr.server_boot, self.transactions[index].id);
+                self.set_tx_event(index, DCERPCUDPEvent::ServerBootChanged);
+            }
+            _ => {}
+        }
+    }
+
+    /// Set `SerialInterfaceConflict` if the serial number of a request was
+    /// already used by a request of the same activity for another interface.
+    /// The serial number identifies a call within its activity, see
+    /// `check_serial`.
+    fn check_serial_interface(&mut self, index: usize, hdr: &DCERPCHdrUdp) {
+        let key = (hdr.activityuuid, hdr.get_serial());
+        match self.serial_interfaces.get(&key) {
+            Some(iface) if *iface != hdr.interfaceuuid => {
+                SCLogDebug!("serial {} used with interfaces {} and {}", key.1, iface,
+                            hdr.interfaceuuid);
+                self.set_tx_event(index, DCERPCUDPEvent::SerialInterfaceConflict);
+            }
+            Some(_) => {}
+            None if self.serial_interfaces.len() < DCERPC_UDP_MAX_SERIALS => {
+                self.serial_interfaces.insert(key, hdr.interfaceuuid);
+            }
+            None => {}
+        }
+    }
+
+    /// Set `UnexpectedStubData` on the call of a control PDU that carries
+    /// more than the body its type defines. Data PDUs are not checked, nor
+    /// are PDUs for calls that are not known.
+    fn check_control_body(&mut self, hdr: &DCERPCHdrUdp, input: &[u8]) {
+        let endianness = match hdr.get_hdr_drep_0() {
+            Ok(drep) if drep & 0x10 != 0 => Endianness::Little,
+            _ => Endianness::Big,
+        };
+        let body_len = match hdr.get_hdr_pkt_type() {
+            Some(DCERPCPktType::Ping | DCERPCPktType::Ack | DCERPCPktType::Working) => 0,
+            // cancel version and id, and whether the server accepts it
+            Some(DCERPCPktType::Cancel) => 8,
+            Some(DCERPCPktType::CancelAck) => 12,
+            // a nocall may carry a fack body
+            Some(DCERPCPktType::Fack | DCERPCPktType::Nocall) => {
+                match parser::parse_fack_body(input, endianness) {
+                    Ok((rem, _)) => input.len() - rem.len(),
+                    Err(_) => 0,
+                }
+            }
+            _ => return,
+        };
+        if input.len() <= body_len {
+            return;
+        }
+        let index = match self.transactions.iter().position(|tx| {
+            tx.seqnum == hdr.seqnum && tx.activityuuid == hdr.activityuuid
+        }) {
+            Some(index) => index,
+            None => return,
+        };
+        SCLogDebug!("{} bytes of stub data in a {} PDU", input.len() - body_len, hdr.pkt_type);
+        self.set_tx_event(index, DCERPCUDPEvent::UnexpectedStubData);
+    }
+
+    /// Set `ContradictoryFragFlags` if a PDU that is not fragmented has a
+    /// non-zero fragment number. Connectionless PDUs have no first fragment
+    /// flag, a PDU without `PFCL1_FRAG` is both the first and the last
+    /// fragment, so its fragment number must be 0.
+    fn check_frag_flags(&mut self, index: usize, hdr: &DCERPCHdrUdp) {
+        if hdr.flags1 & PFCL1_FRAG == 0 && hdr.fragnum != 0 {
+            SCLogDebug!("unfragmented PDU with fragnum {}", hdr.fragnum);
+            self.set_tx_event(index, DCERPCUDPEvent::ContradictoryFragFlags);
+        }
+    }
+
+    /// Set `NonMonotonicFragnum` if a fragment doesn't follow the previous
+    /// fragment of the transaction. Fragment 0 starts a new PDU and is not
+    /// checked.
+    fn check_fragnum(&mut self, index: usize, hdr: &DCERPCHdrUdp) {
+        let tx = &self.transactions[index];
+        let frag_cnt = if hdr.get_hdr_pkt_type() == Some(DCERPCPktType::Request) {
+            tx.frag_cnt_ts
+        } else {
+            tx.frag_cnt_tc
+        };
+        if frag_cnt > 0 && hdr.fragnum != 0 && hdr.fragnum <= tx.udp().fragnum {
+            SCLogDebug!("fragnum {} after fragnum {}", hdr.fragnum, tx.udp().fragnum);
+            self.set_tx_event(index, DCERPCUDPEvent::NonMonotonicFragnum);
+        }
+    }
+
+    /// Set `FraglenTooSmall` if a fragment that is not the last one of its
+    /// PDU carries less than `DCERPC_UDP_MIN_FRAGLEN` bytes.
+    fn check_fraglen(&mut self, index: usize, hdr: &DCERPCHdrUdp) {
+        let last = (hdr.flags1 & PFCL1_FRAG) == 0 || (hdr.flags1 & PFCL1_LASTFRAG) != 0;
+        if !last && hdr.fraglen < DCERPC_UDP_MIN_FRAGLEN {
+            SCLogDebug!("fraglen {} of fragment {} too small", hdr.fraglen, hdr.fragnum);
+            self.set_tx_event(index, DCERPCUDPEvent::FraglenTooSmall);
+        }
+    }
+
+    /// Set `UnexpectedByteOrder` if the integer byte order of the PDU is not
+    /// one of the allowed byte orders. The PDU is still processed.
+    fn check_byte_order(&mut self, index: usize, hdr: &DCERPCHdrUdp) {
+        let drep = match hdr.get_hdr_drep_0() {
+            Ok(drep) => drep,
+            Err(_) => return,
+        };
+        let byte_order = if drep & 0x10 != 0 {
+            DCERPC_UDP_BYTE_ORDER_LITTLE
+        } else {
+            DCERPC_UDP_BYTE_ORDER_BIG
+        };
+        if self.allowed_byte_orders & byte_order == 0 {
+            SCLogDebug!("unexpected byte order {:#x} for tx id {}", drep,
+                        self.transactions[index].id);
+            self.set_tx_event(index, DCERPCUDPEvent::UnexpectedByteOrder);
+        }
+    }
+
+    /// Handle a gap notification. Any buffered partial header is dropped and
+    /// transactions with a partially reassembled stub are closed, so data
+    /// following the gap is not appended to them.
+    pub fn handle_gap(&mut self) {
+        self.buffer.clear();
+        for tx in &mut self.transactions {
+            if !tx.req_done && tx.frag_cnt_ts > 0 {
+                tx.req_lost = true;
+                tx.req_done = true;
+            }
+            if !tx.resp_done && tx.frag_cnt_tc > 0 {
+                tx.resp_lost = true;
+                tx.resp_done = true;
+            }
+        }
+        self.notify_completed();
+    }
+
+    /// Handle the end of the flow: open transactions are finalized and
+    /// partially reassembled ones are marked as lost.
+    pub fn handle_eof(&mut self) {
+        self.buffer.clear();
+        if self.reorder_tolerance {
+            let unrequested: Vec<usize> = self
+                .transactions
+                .iter()
+                .enumerate()
+                .filter(|(_, tx)| {
+                    tx.frag_cnt_ts == 0 && tx.frag_cnt_tc > 0 && !tx.udp().broadcast
+                        && !tx.req_done
+                })
+                .map(|(index, _)| index)
+                .collect();
+            for index in unrequested {
+                self.set_tx_event(index, DCERPCUDPEvent::ResponseWithoutRequest);
+            }
+        }
+        for tx in &mut self.transactions {
+            if !tx.req_done {
+                tx.req_lost = tx.frag_cnt_ts > 0;
+                tx.req_done = true;
+            }
+            if !tx.resp_done {
+                tx.resp_lost = tx.frag_cnt_tc > 0;
+                tx.resp_done = true;
+            }
+        }
+        self.notify_completed();
+    }
+
+    /// Parse a datagram and describe what was done with it.
+    pub fn parse_datagram(&mut self, input: &[u8]) -> UdpParseOutcome {
+        self.datagram_events.clear();
+        let mut outcome = self.parse_pdus(input);
+        outcome.events = std::mem::take(&mut self.datagram_events);
+        self.notify_completed();
+        outcome
+    }
+
+    /// Call the completion callback for transactions that completed since
+    /// the last call.
+    fn notify_completed(&mut self) {
+        let callback = match self.tx_complete_cb {
+            Some(callback) => callback,
+            None => return,
+        };
+        for tx in self.transactions.iter_mut() {
+            if tx.req_done && tx.resp_done && !tx.udp().complete_notified {
+                tx.udp_mut().complete_notified = true;
+                (callback.cb)(tx, callback.user_data);
+            }
+        }
+    }
+
+    /// Parse a datagram. `LargeDatagram` is set on the last transaction
+    /// when the datagram is larger than `large_datagram_threshold`, it is
+    /// parsed all the same.
+    pub fn handle_input_data(&mut self, input: &[u8]) -> AppLayerResult {
+        let result = self.parse_datagram(input).into();
+        if self.large_datagram_threshold != 0 && input.len() > self.large_datagram_threshold as usize {
+            SCLogDebug!("datagram of {} bytes", input.len());
+            self.set_event(DCERPCUDPEvent::LargeDatagram);
+        }
+        result
+    }
+
+    fn parse_pdus(&mut self, input: &[u8]) -> UdpParseOutcome {
+        self.tick += 1;
+        self.stats.datagrams += 1;
+        let mut v: Vec<u8>;
+        let buffer = if self.buffer.is_empty() {
+            input
+        } else {
+            v = self.buffer.split_off(0);
+            v.extend_from_slice(input);
+            v.as_slice()
+        };
+
+        // A datagram may carry several PDUs back to back, parse them all.
+        // All lengths are kept as usize and checked against the remaining
+        // input before it is sliced.
+        let mut rest = buffer;
+        loop {
+            let (consumed, header) = match self.process_header(rest) {
+                Ok(v) => v,
+                Err(HeaderError::Incomplete(needed)) => {
+                    // Wait for the rest of the header if it was split
+                    // across calls
+                    SCLogDebug!("Partial header ({} bytes), waiting for more data..", rest.len());
+                    self.buffer.extend_from_slice(rest);
+                    return UdpParseOutcome::incomplete(input.len(), needed);
+                }
+                Err(_err) => {
+                    SCLogDebug!("DCERPC UDP header did not validate: {:?}", _err);
+                    self.stats.headers_err += 1;
+                    return UdpParseOutcome::err();
+                }
+            };
+            let leftover_bytes = &rest[consumed..];
+            if leftover_bytes.len() < header.fraglen as usize {
+                // Short read of the body, keep the PDU until the rest of it
+                // arrives. Its header is parsed again then, so it is only
+                // counted once the PDU is complete.
+                let needed = header.fraglen as usize - leftover_bytes.len();
+                SCLogDebug!("Insufficient data: leftover_bytes {}, fraglen {}, need {} more",
+                            leftover_bytes.len(), header.fraglen, needed);
+                self.buffer.extend_from_slice(rest);
+                return UdpParseOutcome::incomplete(input.len(), needed);
+            }
+            self.stats.headers_ok += 1;
+            let (body, trailer) = leftover_bytes.split_at(header.fraglen as usize);
+            // fraglen does not cover the auth verifier, it is whatever
+            // follows the body in the datagram
+            self.auth_len = if header.auth_proto != 0 { trailer.len() as u32 } else { 0 };
+            if self.metadata_only {
+                self.handle_metadata(&header);
+            } else if !self.handle_fragment_data(&header, body) {
+                self.stats.reassembly_failures += 1;
+                return UdpParseOutcome::err();
+            }
+            // For authenticated PDUs the auth verifier follows the body and
+            // is not part of the stub data. Otherwise anything that looks
+            // like a header is another PDU.
+            if header.auth_proto != 0 || !probe(trailer).0 {
+                SCLogDebug!("auth_proto {}, trailer of {} bytes", header.auth_proto,
+                            trailer.len());
+                break;
+            }
+            SCLogDebug!("next PDU in the same datagram, {} bytes left", trailer.len());
+            rest = trailer;
+        }
+        return UdpParseOutcome::ok(input.len());
+    }
+
+    /// Parse and validate the header at the start of `input`, and keep its
+    /// raw bytes for the transaction. Returns the number of bytes of the
+    /// header and the header itself.
+    fn process_header(&mut self, input: &[u8]) -> Result<(usize, DCERPCHdrUdp), HeaderError> {
+        let hdr_len = DCERPC_UDP_HDR_LEN as usize;
+        let header = parse_header(input)?;
+        self.raw_header = input[..hdr_len].try_into().ok();
+        Ok((hdr_len, header))
+    }
+}
+
+/// Parse and validate the connectionless header at the start of `input`.
+/// Nothing but the input is looked at, so it can be used to check a header
+/// outside of a flow.
+pub fn parse_header(input: &[u8]) -> Result<DCERPCHdrUdp, HeaderError> {
+    match parse_header_with_len(input)? {
+        (consumed, header) if consumed == DCERPC_UDP_HDR_LEN as usize => Ok(header),
+        _ => Err(HeaderError::Malformed),
+    }
+}
+
+/// Interface UUID and opnum of the connectionless header at the start of
+/// `input`, read from their offsets without parsing or validating the rest
+/// of the header. For prefiltering, before a flow goes through the parser.
+pub fn peek_iface_opnum(input: &[u8]) -> Option<(DCERPCUuid, u16)> {
+    if input.len() < DCERPC_UDP_HDR_LEN as usize || input[0] != 4 {
+        return None;
+    }
+    let mut iface = DCERPCUuid::from_bytes(&input[24..40])?;
+    iface.0[0..4].reverse();
+    iface.0[4..6].reverse();
+    iface.0[6..8].reverse();
+    let opnum = [input[68], input[69]];
+    let opnum = if input[4] == 0 { u16::from_be_bytes(opnum) } else { u16::from_le_bytes(opnum) };
+    Some((iface, opnum))
+}
+
+/// `parse_header`, also returning the number of bytes the header parser
+/// consumed.
+fn parse_header_with_len(input: &[u8]) -> Result<(usize, DCERPCHdrUdp), HeaderError> {
+    let hdr_len = DCERPC_UDP_HDR_LEN as usize;
+    if input.len() < hdr_len {
+        // A partial header that already has the wrong version will never
+        // validate, don't wait for the rest of it
+        if matches!(input.first(), Some(&rpc_vers) if rpc_vers != 4) {
+            return Err(HeaderError::BadVersion);
+        }
+        return Err(HeaderError::Incomplete(hdr_len - input.len()));
+    }
+    let (consumed, header) = match parser::parse_dcerpc_udp_header(input) {
+        Ok((leftover_bytes, header)) => (input.len() - leftover_bytes.len(), header),
+        Err(Err::Incomplete(Needed::Size(needed))) => {
+            return Err(HeaderError::Incomplete(needed.get()));
+        }
+        Err(_) => {
+            return Err(HeaderError::Malformed);
+        }
+    };
+    if header.rpc_vers != 4 {
+        return Err(HeaderError::BadVersion);
+    }
+    // Same checks as the probe, which only sees the first PDU
+    let drep_valid = matches!(header.get_hdr_drep_0(), Ok(drep) if drep & 0xee == 0)
+        && matches!(header.get_hdr_drep_1(), Ok(drep) if drep <= 3);
+    if !drep_valid {
+        return Err(HeaderError::Malformed);
+    }
+    Ok((consumed, header))
+}
+
+/// Character format in the data representation label
+pub const DCERPC_DREP_CHAR_ASCII: u8 = 0x00;
+pub const DCERPC_DREP_CHAR_EBCDIC: u8 = 0x01;
+
+/// EBCDIC (code page 037) to ISO 8859-1 conversion table.
+static EBCDIC_TO_ASCII: [u8; 256] = [
+    0x00, 0x01, 0x02, 0x03, 0x9c, 0x09, 0x86, 0x7f, 0x97, 0x8d, 0x8e, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+    0x10, 0x11, 0x12, 0x13, 0x9d, 0x85, 0x08, 0x87, 0x18, 0x19, 0x92, 0x8f, 0x1c, 0x1d, 0x1e, 0x1f,
+    0x80, 0x81, 0x82, 0x83, 0x84, 0x0a, 0x17, 0x1b, 0x88, 0x89, 0x8a, 0x8b, 0x8c, 0x05, 0x06, 0x07,
+    0x90, 0x91, 0x16, 0x93, 0x94, 0x95, 0x96, 0x04, 0x98, 0x99, 0x9a, 0x9b, 0x14, 0x15, 0x9e, 0x1a,
+    0x20, 0xa0, 0xe2, 0xe4, 0xe0, 0xe1, 0xe3, 0xe5, 0xe7, 0xf1, 0xa2, 0x2e, 0x3c, 0x28, 0x2b, 0x7c,
+    0x26, 0xe9, 0xea, 0xeb, 0xe8, 0xed, 0xee, 0xef, 0xec, 0xdf, 0x21, 0x24, 0x2a, 0x29, 0x3b, 0xac,
+    0x2d, 0x2f, 0xc2, 0xc4, 0xc0, 0xc1, 0xc3, 0xc5, 0xc7, 0xd1, 0xa6, 0x2c, 0x25, 0x5f, 0x3e, 0x3f,
+    0xf8, 0xc9, 0xca, 0xcb, 0xc8, 0xcd, 0xce, 0xcf, 0xcc, 0x60, 0x3a, 0x23, 0x40, 0x27, 0x3d, 0x22,
+    0xd8, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0xab, 0xbb, 0xf0, 0xfd, 0xfe, 0xb1,
+    0xb0, 0x6a, 0x6b, 0x6c, 0x6d, 0x6e, 0x6f, 0x70, 0x71, 0x72, 0xaa, 0xba, 0xe6, 0xb8, 0xc6, 0xa4,
+    0xb5, 0x7e, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0xa1, 0xbf, 0xd0, 0xdd, 0xde, 0xae,
+    0x5e, 0xa3, 0xa5, 0xb7, 0xa9, 0xa7, 0xb6, 0xbc, 0xbd, 0xbe, 0x5b, 0x5d, 0xaf, 0xa8, 0xb4, 0xd7,
+    0x7b, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0xad, 0xf4, 0xf6, 0xf2, 0xf3, 0xf5,
+    0x7d, 0x4a, 0x4b, 0x4c, 0x4d, 0x4e, 0x4f, 0x50, 0x51, 0x52, 0xb9, 0xfb, 0xfc, 0xf9, 0xfa, 0xff,
+    0x5c, 0xf7, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0xb2, 0xd4, 0xd6, 0xd2, 0xd3, 0xd5,
+    0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0xb3, 0xdb, 0xdc, 0xd9, 0xda, 0x9f,
+];
+
+impl DCERPCTransaction {
+    /// One line description of the transaction for debug logging, without
+    /// the stub data itself.
+    pub fn summary(&self) -> String {
+        format!(
+            "tx id {} call_id {} {}/{} opnum {} seqnum {} frag_cnt {}/{} stub_data_size {}/{}",
+            self.id, self.call_id, dcerpc_type_string(self.req_cmd),
+            dcerpc_type_string(self.resp_cmd), self.opnum, self.seqnum, self.frag_cnt_ts,
+            self.frag_cnt_tc, self.stub_data_buffer_ts.len(), self.stub_data_buffer_tc.len()
+        )
+    }
+
+    /// Owned copy of the data of the transaction, for processing outside of
+    /// the parser.
+    pub fn snapshot(&self) -> TxSnapshot {
+        TxSnapshot {
+            id: self.id,
+            seqnum: self.seqnum,
+            opnum: self.opnum,
+            pkt_type: self.udp().pkt_type,
+            activityuuid: self.activityuuid,
+            interfaceuuid: self.udp().interfaceuuid,
+            if_vers: self.udp().if_vers,
+            req_done: self.req_done,
+            resp_done: self.resp_done,
+            req_lost: self.req_lost,
+            resp_lost: self.resp_lost,
+            frag_cnt_ts: self.frag_cnt_ts,
+            frag_cnt_tc: self.frag_cnt_tc,
+            fault_status: self.udp().fault_status,
+            auth_type: self.udp().auth_type,
+            stub_data_ts: self.stub_data_buffer_ts.clone(),
+            stub_data_tc: self.stub_data_buffer_tc.clone(),
+        }
+    }
+
+    /// Stub data buffered in both directions.
+    fn buffered_stub_len(&self) -> u64 {
+        (self.stub_data_buffer_ts.len() + self.stub_data_buffer_tc.len()) as u64
+    }
+
+    /// Fraction of the stub data of both directions that was received, from
+    /// 0.0 to 1.0. Fragments missing below the highest fragment number seen
+    /// are counted with the length of the largest fragment received.
+    /// Transactions without stub data are complete.
+    pub fn reassembly_completeness(&self) -> f32 {
+        let missing = |total: u16, cnt: u16, len_max: u32| {
+            total.saturating_sub(cnt) as u64 * len_max as u64
+        };
+        let received = self.udp().stub_data_expected_len_ts as u64 + self.udp().stub_data_expected_len_tc as u64;
+        let expected = received
+            + missing(self.udp().stub_frag_total_ts, self.frag_cnt_ts, self.udp().stub_frag_len_max_ts)
+            + missing(self.udp().stub_frag_total_tc, self.frag_cnt_tc, self.udp().stub_frag_len_max_tc);
+        if expected == 0 {
+            return 1.0;
+        }
+        (received as f64 / expected as f64) as f32
+    }
+
+    /// Name of the requested operation, if the interface is a well-known one.
+    pub fn get_operation_name(&self) -> Option<&'static str> {
+        resolve_operation(self.udp().interfaceuuid.as_bytes(), self.opnum)
+    }
+
+    /// Major and minor version of the requested interface. The major
+    /// version is in the low 16 bits of `if_vers`.
+    pub fn get_iface_version(&self) -> (u16, u16) {
+        ((self.udp().if_vers & 0xffff) as u16, (self.udp().if_vers >> 16) as u16)
+    }
+
+    /// Whether a response PDU can belong to this transaction. Broadcast
+    /// calls get a response transaction per server, told apart by their
+    /// boot time.
+    fn is_response_from(&self, hdr: &DCERPCHdrUdp) -> bool {
+        !self.udp().broadcast || self.frag_cnt_tc == 0 || self.udp().server_boot == hdr.server_boot
+    }
+
+    /// Whether the last fragment seen on the transaction was the first
+    /// fragment of the PDU.
+    pub fn is_first_frag(&self) -> bool {
+        self.udp().fragnum == 0
+    }
+
+    /// Whether the last fragment seen on the transaction was the last
+    /// fragment of the PDU. Unfragmented PDUs are both first and last.
+    pub fn is_last_frag(&self) -> bool {
+        (self.udp().flags1 & PFCL1_FRAG) == 0 || (self.udp().flags1 & PFCL1_LASTFRAG) != 0
+    }
+
+    /// Whether the transaction has a request, a response or both. A call is
+    /// only `Complete` once both directions are done, until then it is
+    /// described by the side it started with.
+    pub fn conversation_state(&self) -> ConversationState {
+        let request = self.frag_cnt_ts > 0 || !self.stub_data_buffer_ts.is_empty();
+        let response = self.frag_cnt_tc > 0 || !self.stub_data_buffer_tc.is_empty();
+        if request && response && self.req_done && self.resp_done {
+            ConversationState::Complete
+        } else if response && !request {
+            ConversationState::ResponseOnly
+        } else {
+            ConversationState::RequestOnly
+        }
+    }
+
+    /// Extract the endpoints resolved by an endpoint mapper lookup or map
+    /// call from its response.
+    fn parse_epm_endpoints(&mut self) {
+        if !matches!(lookup_interface(self.udp().interfaceuuid.as_bytes()), Some(iface) if iface.name == "epmapper")
+            || !matches!(self.opnum, 2 | 3)
+        {
+            return;
+        }
+        let endianness = if self.endianness & 0x10 != 0 {
+            Endianness::Little
+        } else {
+            Endianness::Big
+        };
+        let towers = match parser::parse_epm_response(&self.stub_data_buffer_tc, self.opnum, endianness) {
+            Ok((_, towers)) => towers,
+            Err(_) => {
+                SCLogDebug!("failed to parse EPM response for {}", self.summary());
+                return;
+            }
+        };
+        self.udp_mut().epm_endpoints = towers
+            .iter()
+            .filter_map(|tower| parser::parse_epm_tower_floors(tower).ok())
+            .filter_map(|(_, floors)| epm_endpoint(&floors))
+            .collect();
+    }
+
+    /// The stub data in `direction` converted to ASCII, for transactions
+    /// that use the EBCDIC character format. `None` otherwise. The
+    /// conversion is cached until more stub data is added.
+    pub fn stub_ascii(&mut self, direction: Direction) -> Option<&[u8]> {
+        if self.udp().charset != DCERPC_DREP_CHAR_EBCDIC {
+            return None;
+        }
+        let udp = self.udp.get_or_insert_with(Default::default);
+        let (stub_data_buffer, cached) = match direction {
+            Direction::ToServer => (&self.stub_data_buffer_ts, &mut udp.stub_ascii_ts),
+            Direction::ToClient => (&self.stub_data_buffer_tc, &mut udp.stub_ascii_tc),
+        };
+        let ascii = cached.get_or_insert_with(|| {
+            stub_data_buffer.iter().map(|&b| EBCDIC_TO_ASCII[b as usize]).collect()
+        });
+        Some(ascii.as_slice())
+    }
+
+    /// Printable UTF-16LE strings found in the stub data in `direction`, such
+    /// as paths and pipe names, converted to UTF-8. The strings are cached
+    /// until more stub data is added.
+    pub fn stub_strings(&mut self, direction: Direction) -> &[String] {
+        let udp = self.udp.get_or_insert_with(Default::default);
+        let (stub_data_buffer, cached) = match direction {
+            Direction::ToServer => (&self.stub_data_buffer_ts, &mut udp.stub_strings_ts),
+            Direction::ToClient => (&self.stub_data_buffer_tc, &mut udp.stub_strings_tc),
+        };
+        cached.get_or_insert_with(|| find_utf16_strings(stub_data_buffer))
+    }
+
+    /// The first `max_len` bytes of the reassembled stub data in
+    /// `direction`.
+    pub fn stub_preview(&self, direction: Direction, max_len: usize) -> &[u8] {
+        let stub_data = self.get_stub_data(direction);
+        &stub_data[..std::cmp::min(max_len, stub_data.len())]
+    }
+
+    /// NDR array counts at the start of the stub data in `direction`, if
+    /// the stub starts with a conformant array.
+    pub fn ndr_prefix(&self, direction: Direction) -> Option<NdrPrefix> {
+        let endianness = if self.endianness & 0x10 != 0 {
+            Endianness::Little
+        } else {
+            Endianness::Big
+        };
+        parse_ndr_prefix(self.stub_preview(direction, 12), endianness)
+    }
+
+    /// SHA-256 of the reassembled stub data in `direction`. The hash is
+    /// cached until more stub data is added to the transaction.
+    pub fn stub_sha256(&mut self, direction: Direction) -> [u8; 32] {
+        let udp = self.udp.get_or_insert_with(Default::default);
+        let (stub_data_buffer, cached) = match direction {
+            Direction::ToServer => (&self.stub_data_buffer_ts, &mut udp.stub_sha256_ts),
+            Direction::ToClient => (&self.stub_data_buffer_tc, &mut udp.stub_sha256_tc),
+        };
+        *cached.get_or_insert_with(|| Sha256::digest(stub_data_buffer).into())
+    }
+}
+
+/// Find runs of at least `DCERPC_UDP_STUB_STRING_MIN_LEN` printable ASCII
+/// characters encoded as UTF-16LE, bounded by `DCERPC_UDP_STUB_STRINGS_MAX`
+/// and `DCERPC_UDP_STUB_STRING_MAX_LEN`.
+fn find_utf16_strings(input: &[u8]) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut i = 0;
+    while i + 1 < input.len() && strings.len() < DCERPC_UDP_STUB_STRINGS_MAX {
+        let mut end = i;
+        while end + 1 < input.len() && (0x20..=0x7e).contains(&input[end]) && input[end + 1] == 0 {
+            end += 2;
+        }
+        let chars = (end - i) / 2;
+        if chars < DCERPC_UDP_STUB_STRING_MIN_LEN {
+            i += 1;
+            continue;
+        }
+        let string = input[i..end]
+            .iter()
+            .step_by(2)
+            .take(DCERPC_UDP_STUB_STRING_MAX_LEN)
+            .map(|&b| b as char)
+            .collect();
+        strings.push(string);
+        i = end;
+    }
+    strings
+}
+
+/// Put a UUID stored by the header parser back in its on the wire order,
+/// undoing the swap of the first three fields.
+fn uuid_to_wire(uuid: &DCERPCUuid) -> [u8; 16] {
+    let mut wire = uuid.0;
+    wire[0..4].reverse();
+    wire[4..6].reverse();
+    wire[6..8].reverse();
+    wire
+}
+
+/// Format the floors of a protocol tower as a string binding, such as
+/// `ncacn_ip_tcp:10.0.0.1[49154]`. `None` if the tower has no transport.
+fn epm_endpoint(floors: &[parser::TowerFloor]) -> Option<String> {
+    let mut protseq = None;
+    let mut endpoint = String::new();
+    let mut host = String::new();
+    let string = |rhs: &[u8]| String::from_utf8_lossy(rhs).trim_end_matches('\0').to_string();
+    for (lhs, rhs) in floors {
+        let port = || match rhs[..] {
+            [hi, lo] => u16::from_be_bytes([hi, lo]).to_string(),
+            _ => String::new(),
+        };
+        match lhs.first() {
+            Some(0x07) => {
+                protseq = Some("ncacn_ip_tcp");
+                endpoint = port();
+            }
+            Some(0x08) => {
+                protseq = Some("ncadg_ip_udp");
+                endpoint = port();
+            }
+            Some(0x1f) => {
+                protseq = Some("ncacn_http");
+                endpoint = port();
+            }
+            Some(0x0f) => {
+                protseq = Some("ncacn_np");
+                endpoint = string(rhs);
+            }
+            Some(0x10) => {
+                protseq = Some("ncalrpc");
+                endpoint = string(rhs);
+            }
+            Some(0x09) if rhs.len() == 4 => {
+                host = format!("{}.{}.{}.{}", rhs[0], rhs[1], rhs[2], rhs[3]);
+            }
+            Some(0x11) => {
+                host = string(rhs);
+            }
+            _ => {}
+        }
+    }
+    Some(format!("{}:{}[{}]", protseq?, host, endpoint))
+}
+
+/// Length of the zero padding that aligns the body of the last fragment to
+/// 8 bytes. Only bodies whose length is a multiple of 8 are considered, and
+/// at most 7 trailing zero bytes are counted, as a longer run is not
+/// alignment. Trailing zeros that are part of the stub can't be told apart
+/// from padding, which is why stripping it is optional.
+fn stub_padding_len(input: &[u8]) -> usize {
+    if input.is_empty() || input.len() & 7 != 0 {
+        return 0;
+    }
+    input.iter().rev().take(7).take_while(|&&b| b == 0).count()
+}
+
+/// Append fragment data to a stub buffer. The first time a buffer receives
+/// data its capacity is reserved from the fragment length, so a PDU is not
+/// grown in small steps while it is being reassembled.
+///
+/// The buffer is never grown past `max` bytes. Returns false if data had to
+/// be dropped to respect that limit.
+fn append_stub_data(stub_data_buffer: &mut Vec<u8>, hdr: &DCERPCHdrUdp, input: &[u8], max: usize) -> bool {
+    if stub_data_buffer.is_empty() {
+        let size = std::cmp::max(hdr.fraglen as usize, input.len());
+        stub_data_buffer.reserve_exact(std::cmp::min(size, max));
+    }
+    let room = max.saturating_sub(stub_data_buffer.len());
+    if input.len() > room {
+        stub_data_buffer.extend_from_slice(&input[..room]);
+        return false;
+    }
+    stub_data_buffer.extend_from_slice(input);
+    true
+}
+
+/// Time of the last update of the flow in microseconds.
+#[cfg(not(test))]
+unsafe fn flow_time_us(flow: *const core::Flow) -> Option<u64> {
+    if flow.is_null() {
+        return None;
+    }
+    let flow = cast_pointer!(flow, core::Flow);
+    Some(flow.get_last_time().as_micros() as u64)
+}
+
+#[cfg(test)]
+unsafe fn flow_time_us(_flow: *const core::Flow) -> Option<u64> {
+    None
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_parse(
+    flow: *const core::Flow, state: *mut std::os::raw::c_void, _pstate: *mut std::os::raw::c_void,
+    stream_slice: StreamSlice,
+    _data: *const std::os::raw::c_void,
+) -> AppLayerResult {
+    let state = cast_pointer!(state, DCERPCUDPState);
+    let flags = stream_slice.flags();
+    if let Some(ts) = flow_time_us(flow) {
+        state.ts = ts;
+    }
+
+    if stream_slice.is_gap() || flags & core::STREAM_GAP != 0 {
+        state.handle_gap();
+        if stream_slice.is_gap() {
+            return AppLayerResult::ok();
+        }
+    }
+    if stream_slice.is_empty() {
+        // nothing to parse, this doesn't make the flow any less DCERPC
+        if flags & core::STREAM_EOF != 0 {
+            state.handle_eof();
+        }
+        return AppLayerResult::ok();
+    }
+    // The slice is built from the length given by the caller, a length no
+    // datagram can have is a caller bug and the buffer can't be trusted
+    if stream_slice.len() > DCERPC_UDP_MAX_INPUT_LEN {
+        SCLogDebug!("input of {} bytes exceeds {}", stream_slice.len(), DCERPC_UDP_MAX_INPUT_LEN);
+        return AppLayerResult::err();
+    }
+    let res = state.handle_input_data(stream_slice.as_slice());
+    if flags & core::STREAM_EOF != 0 {
+        state.handle_eof();
+    }
+    res
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_stats(
+    state: *mut std::os::raw::c_void, stats: *mut DCERPCUDPStats,
+) {
+    let state = cast_pointer!(state, DCERPCUDPState);
+    if stats.is_null() {
+        return;
+    }
+    *stats = state.stats;
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_has_pending(state: *mut std::os::raw::c_void) -> u8 {
+    let state = cast_pointer!(state, DCERPCUDPState);
+    state.has_pending() as u8
+}
+
+/// Drop transactions not updated in the last `max_age` parse calls, e.g. on
+/// flow timeout. Returns the number of transactions dropped.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_evict_stale(
+    state: *mut std::os::raw::c_void, max_age: u64,
+) -> u32 {
+    let state = cast_pointer!(state, DCERPCUDPState);
+    state.evict_stale(max_age) as u32
+}
+
+#[no_mangle]
+pub extern "C" fn rs_dcerpc_udp_state_free(state: *mut std::os::raw::c_void) {
+    std::mem::drop(unsafe { Box::from_raw(state as *mut DCERPCUDPState) });
+}
+
+#[no_mangle]
+pub extern "C" fn rs_dcerpc_udp_state_new(_orig_state: *mut std::os::raw::c_void, _orig_proto: core::AppProto) -> *mut std::os::raw::c_void {
+    let state = DCERPCUDPState::new();
+    let boxed = Box::new(state);
+    return Box::into_raw(boxed) as *mut _;
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_state_transaction_free(
+    state: *mut std::os::raw::c_void, tx_id: u64,
+) {
+    let dce_state = cast_pointer!(state, DCERPCUDPState);
+    SCLogDebug!("freeing tx {}", tx_id);
+    dce_state.free_tx(tx_id);
+}
+
+/// Get the engine data of the transaction. The detection state referenced
+/// from it is owned by the transaction: it is freed when the transaction is
+/// freed or the state is reset, and is never shared between transactions,
+/// so there is no separate call to clear it.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_data(
+    tx: *mut std::os::raw::c_void)
+    -> *mut AppLayerTxData
+{
+    let tx = cast_pointer!(tx, DCERPCTransaction);
+    return &mut tx.tx_data;
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx(
+    state: *mut std::os::raw::c_void, tx_id: u64,
+) -> *mut std::os::raw::c_void {
+    let dce_state = cast_pointer!(state, DCERPCUDPState);
+    match dce_state.get_tx(tx_id) {
+        Some(tx) => {
+            return tx as *const _ as *mut _;
+        },
+        None => {
+            return std::ptr::null_mut();
+        }
+    } 
+}
+
+/// Set the function called with `user_data` when a transaction completes, or
+/// remove it if `cb` is NULL.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_set_tx_complete_callback(
+    state: *mut std::os::raw::c_void,
+    cb: Option<extern "C" fn(*mut DCERPCTransaction, *mut std::os::raw::c_void)>,
+    user_data: *mut std::os::raw::c_void,
+) {
+    let dce_state = cast_pointer!(state, DCERPCUDPState);
+    dce_state.tx_complete_cb = cb.map(|cb| DCERPCUDPTxCompleteCallback { cb, user_data });
+}
+
+/// Get the number of distinct activity UUIDs of the requests and responses
+/// seen on the flow, counted up to `DCERPC_UDP_MAX_ACTIVITIES`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_distinct_activity_count(
+    state: *mut std::os::raw::c_void,
+) -> u32 {
+    let dce_state = cast_pointer!(state, DCERPCUDPState);
+    dce_state.activity_uuids.len() as u32
+}
+
+/// Call `cb` with `user_data` once for each transaction of the state, in
+/// transaction id order.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_foreach_tx(
+    state: *mut std::os::raw::c_void, user_data: *mut std::os::raw::c_void,
+    cb: extern "C" fn(*mut DCERPCTransaction, *mut std::os::raw::c_void),
+) {
+    let dce_state = cast_pointer!(state, DCERPCUDPState);
+    for tx in dce_state.transactions.iter_mut() {
+        cb(tx, user_data);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_frag_cnt(
+    tx: &mut DCERPCTransaction, direction: u8,
+) -> u16 {
+    match direction.into() {
+        Direction::ToServer => tx.frag_cnt_ts,
+        Direction::ToClient => tx.frag_cnt_tc,
+    }
+}
+
+/// Get the integer byte order of the transaction, from the data
+/// representation label of its first PDU: `DCERPC_UDP_BYTE_ORDER_LITTLE` or
+/// `DCERPC_UDP_BYTE_ORDER_BIG`. Multi-byte NDR values in the stub data use
+/// this byte order, so numeric content matches on the stub have to take it
+/// into account.
+#[no_mangle]
+pub extern "C" fn rs_dcerpc_udp_get_tx_byte_order(tx: &mut DCERPCTransaction) -> u8 {
+    if tx.endianness & 0x10 != 0 {
+        DCERPC_UDP_BYTE_ORDER_LITTLE
+    } else {
+        DCERPC_UDP_BYTE_ORDER_BIG
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rs_dcerpc_udp_get_tx_auth_type(tx: &mut DCERPCTransaction) -> u8 {
+    tx.udp().auth_type
+}
+
+#[no_mangle]
+pub extern "C" fn rs_dcerpc_udp_get_tx_auth_len(tx: &mut DCERPCTransaction) -> u32 {
+    tx.udp().auth_len
+}
+
+/// Whether a PDU of the transaction used authentication. The protocol is
+/// available through `rs_dcerpc_udp_get_tx_auth_type`.
+#[no_mangle]
+pub extern "C" fn rs_dcerpc_udp_get_tx_is_authenticated(tx: &mut DCERPCTransaction) -> u8 {
+    (tx.udp().auth_type != 0) as u8
+}
+
+/// Reassembly completeness of the transaction in hundredths of a percent,
+/// from 0 to 10000.
+#[no_mangle]
+pub extern "C" fn rs_dcerpc_udp_get_tx_reassembly_completeness(tx: &mut DCERPCTransaction) -> u16 {
+    (tx.reassembly_completeness() * 10000.0).round() as u16
+}
+
+/// Fill `status` for the transaction with internal id `tx_id`. Returns 1 if
+/// the transaction was found, 0 otherwise.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_status(
+    state: *mut std::os::raw::c_void, tx_id: u64, status: *mut DCERPCUDPTxStatus,
+) -> u8 {
+    let state = cast_pointer!(state, DCERPCUDPState);
+    if status.is_null() {
+        return 0;
+    }
+    match state.tx_status(tx_id) {
+        Some(s) => {
+            *status = s.into();
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Fill `snapshot` with a copy of the data of the transaction. Returns 0 if
+/// `snapshot` is null.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_snapshot(
+    tx: &mut DCERPCTransaction, snapshot: *mut DCERPCUDPTxSnapshot,
+) -> u8 {
+    if snapshot.is_null() {
+        return 0;
+    }
+    *snapshot = (&tx.snapshot()).into();
+    1
+}
+
+/// Get the stub data converted from EBCDIC to ASCII. Returns 0 if the
+/// transaction does not use the EBCDIC character format, the raw stub data
+/// is available through `rs_dcerpc_get_stub_data` in all cases.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_stub_data_ascii(
+    tx: &mut DCERPCTransaction, buf: *mut *const u8, len: *mut u32, dir: u8,
+) -> u8 {
+    if buf.is_null() || len.is_null() {
+        return 0;
+    }
+    match tx.stub_ascii(dir.into()) {
+        Some(ascii) => {
+            *buf = ascii.as_ptr();
+            *len = ascii.len() as u32;
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Get the maximum element count declared by the NDR array prefix of the
+/// stub data in `dir`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_ndr_max_count(
+    tx: &mut DCERPCTransaction, dir: u8, max_count: *mut u32,
+) -> u8 {
+    if max_count.is_null() {
+        return 0;
+    }
+    match tx.ndr_prefix(dir.into()) {
+        Some(prefix) => {
+            *max_count = prefix.max_count;
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Get up to `max_len` bytes from the start of the stub data in `dir`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_stub_preview(
+    tx: &mut DCERPCTransaction, dir: u8, max_len: u32, buf: *mut *const u8, len: *mut u32,
+) -> u8 {
+    if buf.is_null() || len.is_null() {
+        return 0;
+    }
+    let preview = tx.stub_preview(dir.into(), max_len as usize);
+    *buf = preview.as_ptr();
+    *len = preview.len() as u32;
+    (!preview.is_empty()) as u8
+}
+
+/// Get the UTF-16LE string at index `idx` found in the stub data in `dir`,
+/// converted to UTF-8. Returns 0 when there is no such string.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_stub_string(
+    tx: &mut DCERPCTransaction, dir: u8, idx: u32, buf: *mut *const u8, len: *mut u32,
+) -> u8 {
+    if buf.is_null() || len.is_null() {
+        return 0;
+    }
+    match tx.stub_strings(dir.into()).get(idx as usize) {
+        Some(string) => {
+            *buf = string.as_ptr();
+            *len = string.len() as u32;
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Write the SHA-256 of the stub data in `direction` to `out`, which must
+/// point to at least 32 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_stub_sha256(
+    tx: &mut DCERPCTransaction, direction: u8, out: *mut u8,
+) {
+    if out.is_null() {
+        return;
+    }
+    let hash = tx.stub_sha256(direction.into());
+    std::ptr::copy_nonoverlapping(hash.as_ptr(), out, hash.len());
+}
+
+/// Get the raw header of the PDU that started the transaction, an empty
+/// buffer if it was not kept.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_header_bytes(
+    tx: &mut DCERPCTransaction, buf: *mut *const u8, len: *mut u32,
+) {
+    if buf.is_null() || len.is_null() {
+        return;
+    }
+    match &tx.udp().header_bytes {
+        Some(header) => {
+            *buf = header.as_ptr();
+            *len = header.len() as u32;
+        }
+        None => {
+            *buf = std::ptr::null();
+            *len = 0;
+        }
+    }
+}
+
+/// Get the object UUID of the transaction. Returns 0 and an empty buffer if
+/// the request did not address an object.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_object_uuid(
+    tx: &mut DCERPCTransaction, buf: *mut *const u8, len: *mut u32,
+) -> u8 {
+    if buf.is_null() || len.is_null() {
+        return 0;
+    }
+    match &tx.udp().objectuuid {
+        Some(uuid) => {
+            *buf = uuid.as_bytes().as_ptr();
+            *len = 16;
+            1
+        }
+        None => {
+            *buf = std::ptr::null();
+            *len = 0;
+            0
+        }
+    }
+}
+
+/// Get the number of stub bytes received in each direction, over all the
+/// PDUs of the transaction.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_byte_counts(
+    tx: &mut DCERPCTransaction, bytes_ts: *mut u64, bytes_tc: *mut u64,
+) {
+    if bytes_ts.is_null() || bytes_tc.is_null() {
+        return;
+    }
+    *bytes_ts = tx.udp().bytes_ts;
+    *bytes_tc = tx.udp().bytes_tc;
+}
+
+/// Get the time in microseconds the first request and response fragment of
+/// the transaction were seen, 0 if not seen.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_timestamps(
+    tx: &mut DCERPCTransaction, req_ts: *mut u64, resp_ts: *mut u64,
+) {
+    if req_ts.is_null() || resp_ts.is_null() {
+        return;
+    }
+    *req_ts = tx.udp().req_ts;
+    *resp_ts = tx.udp().resp_ts;
+}
+
+/// Get the `idx`th distinct interface UUID requested on the flow.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_interface_uuid(
+    state: *mut std::os::raw::c_void, idx: u32, buf: *mut *const u8, len: *mut u32,
+) -> u8 {
+    let state = cast_pointer!(state, DCERPCUDPState);
+    if buf.is_null() || len.is_null() {
+        return 0;
+    }
+    if let Some(uuid) = state.interface_uuids.get(idx as usize) {
+        *buf = uuid.as_bytes().as_ptr();
+        *len = 16;
+        return 1;
+    }
+    return 0;
+}
+
+/// Get the interface and activity hints of the transaction.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_hints(
+    tx: &mut DCERPCTransaction, ihint: *mut u16, ahint: *mut u16,
+) {
+    if ihint.is_null() || ahint.is_null() {
+        return;
+    }
+    *ihint = tx.udp().ihint;
+    *ahint = tx.udp().ahint;
+}
+
+/// Get the major and minor version of the requested interface.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_iface_version(
+    tx: &mut DCERPCTransaction, major: *mut u16, minor: *mut u16,
+) {
+    if major.is_null() || minor.is_null() {
+        return;
+    }
+    let (vers_major, vers_minor) = tx.get_iface_version();
+    *major = vers_major;
+    *minor = vers_minor;
+}
+
+#[no_mangle]
+pub extern "C" fn rs_dcerpc_udp_get_tx_fragment_log_cnt(tx: &mut DCERPCTransaction) -> u32 {
+    tx.udp().fragment_log.len() as u32
+}
+
+/// Get the `idx`th fragment recorded for the transaction, in the order the
+/// fragments were received.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_fragment_log(
+    tx: &mut DCERPCTransaction, idx: u32, frag: *mut DCERPCFragMeta,
+) -> u8 {
+    if frag.is_null() {
+        return 0;
+    }
+    match tx.udp().fragment_log.get(idx as usize) {
+        Some(meta) => {
+            *frag = *meta;
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Get the status of a call that failed with a fault PDU.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_fault_status(
+    tx: &mut DCERPCTransaction, status: *mut u32,
+) -> u8 {
+    if status.is_null() {
+        return 0;
+    }
+    match tx.udp().fault_status {
+        Some(fault_status) => {
+            *status = fault_status;
+            1
+        }
+        None => 0,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rs_dcerpc_udp_get_tx_response_opnum(tx: &mut DCERPCTransaction) -> u16 {
+    tx.udp().resp_opnum
+}
+
+/// Get the endpoint at index `idx` resolved by an endpoint mapper call.
+/// Returns 0 when there is no such endpoint.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_epm_endpoints(
+    tx: &mut DCERPCTransaction, idx: u32, buf: *mut *const u8, len: *mut u32,
+) -> u8 {
+    if buf.is_null() || len.is_null() {
+        return 0;
+    }
+    match tx.udp().epm_endpoints.get(idx as usize) {
+        Some(endpoint) => {
+            *buf = endpoint.as_ptr();
+            *len = endpoint.len() as u32;
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Get the fragment numbers acknowledged by fack PDUs, sorted. Returns 0
+/// when no fragment was acknowledged.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_acked_frags(
+    tx: &mut DCERPCTransaction, buf: *mut *const u16, len: *mut u32,
+) -> u8 {
+    if buf.is_null() || len.is_null() {
+        return 0;
+    }
+    if tx.udp().acked_frags.is_empty() {
+        return 0;
+    }
+    *buf = tx.udp().acked_frags.as_ptr();
+    *len = tx.udp().acked_frags.len() as u32;
+    1
+}
+
+/// Get the `ConversationState` of the transaction.
+#[no_mangle]
+pub extern "C" fn rs_dcerpc_udp_get_tx_conversation_state(tx: &mut DCERPCTransaction) -> u8 {
+    tx.conversation_state() as u8
+}
+
+#[no_mangle]
+pub extern "C" fn rs_dcerpc_udp_tx_is_sensitive(tx: &mut DCERPCTransaction) -> u8 {
+    tx.udp().sensitive as u8
+}
+
+#[no_mangle]
+pub extern "C" fn rs_dcerpc_udp_get_tx_fragnum(tx: &mut DCERPCTransaction) -> u16 {
+    tx.udp().fragnum
+}
+
+pub const DCERPC_UDP_FRAG_FIRST: u8 = 0x01;
+pub const DCERPC_UDP_FRAG_LAST: u8 = 0x02;
+
+/// Match the position of the last fragment seen on the transaction. `frag`
+/// is a combination of `DCERPC_UDP_FRAG_FIRST` and `DCERPC_UDP_FRAG_LAST`,
+/// all the bits set have to match.
+#[no_mangle]
+pub extern "C" fn rs_dcerpc_udp_tx_match_frag(tx: &mut DCERPCTransaction, frag: u8) -> u8 {
+    if frag & DCERPC_UDP_FRAG_FIRST != 0 && !tx.is_first_frag() {
+        return 0;
+    }
+    if frag & DCERPC_UDP_FRAG_LAST != 0 && !tx.is_last_frag() {
+        return 0;
+    }
+    1
+}
+
+/// Match the interface and opnum of a request. `iface` points to the 16 byte
+/// interface UUID, in the byte order used by the parser.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_match_iface_opnum(
+    tx: &mut DCERPCTransaction, iface: *const u8, opnum: u16,
+) -> u8 {
+    if iface.is_null() {
+        return 0;
+    }
+    if tx.opnum != opnum {
+        return 0;
+    }
+    let iface = std::slice::from_raw_parts(iface, 16);
+    if tx.udp().interfaceuuid.as_bytes() != iface {
+        return 0;
+    }
+    1
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_tx_cnt(vtx: *mut std::os::raw::c_void) -> u64 {
+    let dce_state = cast_pointer!(vtx, DCERPCUDPState);
+    dce_state.tx_id
+}
+
+/// Value of `rs_dcerpc_udp_get_current_tx_id` before any transaction is
+/// created.
+pub const DCERPC_UDP_NO_TX: u64 = u64::MAX;
+
+/// Get the internal id of the most recently created transaction, or
+/// `DCERPC_UDP_NO_TX`.
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_get_current_tx_id(state: *mut std::os::raw::c_void) -> u64 {
+    let dce_state = cast_pointer!(state, DCERPCUDPState);
+    if dce_state.tx_id == 0 {
+        return DCERPC_UDP_NO_TX;
+    }
+    dce_state.tx_id - 1
+}
+
+/// Probe input to see if it looks like DCERPC.
+fn probe(input: &[u8]) -> (bool, bool) {
+    match parser::parse_dcerpc_udp_header(input) {
+        Ok((_, hdr)) => {
+            let is_request = hdr.get_hdr_pkt_type() == Some(DCERPCPktType::Request);
+            let is_dcerpc = hdr.rpc_vers == 0x04 &&
+                (hdr.flags2 & 0xfc == 0) &&
+                matches!(hdr.get_hdr_drep_0(), Ok(drep) if drep & 0xee == 0) &&
+                matches!(hdr.get_hdr_drep_1(), Ok(drep) if drep <= 3);
+            return (is_dcerpc, is_request);
+        },
+        Err(_) => (false, false),
+    }
+}
+
+pub unsafe extern "C" fn rs_dcerpc_probe_udp(_f: *const core::Flow, direction: u8, input: *const u8,
+                                      len: u32, rdir: *mut u8) -> core::AppProto
+{
+    SCLogDebug!("Probing the packet for DCERPC/UDP");
+    if len == 0 {
+        return core::ALPROTO_UNKNOWN;
+    }
+    let slice: &[u8] = std::slice::from_raw_parts(input as *mut u8, len as usize);
+    //is_incomplete is checked by caller
+    let (is_dcerpc, is_request) = probe(slice);
+    if is_dcerpc {
+        let dir: Direction = (direction & DIR_BOTH).into();
+        if is_request {
+            if dir != Direction::ToServer {
+                *rdir = Direction::ToServer.into();
+            }
+        } else if dir != Direction::ToClient {
+            *rdir = Direction::ToClient.into();
+        };
+        return ALPROTO_DCERPC;
+    }
+    return core::ALPROTO_FAILED;
+}
+
+fn register_pattern_probe() -> i8 {
+    unsafe {
+        if AppLayerProtoDetectPMRegisterPatternCSwPP(core::IPPROTO_UDP, ALPROTO_DCERPC,
+                                                     b"|04 00|\0".as_ptr() as *const std::os::raw::c_char, 2, 0,
+                                                     Direction::ToServer.into(), rs_dcerpc_probe_udp, 0, 0) < 0 {
+            SCLogDebug!("TOSERVER => AppLayerProtoDetectPMRegisterPatternCSwPP FAILED");
+            return -1;
+        }
+    }
+    0
+}
+
+export_state_data_get!(rs_dcerpc_udp_get_state_data, DCERPCUDPState);
+
+/// Progress of a transaction in a direction once the request, or the
+/// response, is done. The same in both directions.
+pub const DCERPC_UDP_PROGRESS_DONE: std::os::raw::c_int = 1;
+
+#[no_mangle]
+pub extern "C" fn rs_dcerpc_udp_get_alstate_progress_completion_status(
+    _direction: u8,
+) -> std::os::raw::c_int {
+    DCERPC_UDP_PROGRESS_DONE
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rs_dcerpc_udp_register_parser() {
+    let parser = RustParser {
+        name: PARSER_NAME.as_ptr() as *const std::os::raw::c_char,
+        default_port: std::ptr::null(),
+        ipproto: core::IPPROTO_UDP,
+        probe_ts: None,
+        probe_tc: None,
+        min_depth: 0,
+        max_depth: 16,
+        state_new: rs_dcerpc_udp_state_new,
+        state_free: rs_dcerpc_udp_state_free,
+        tx_free: rs_dcerpc_udp_state_transaction_free,
+        parse_ts: rs_dcerpc_udp_parse,
+        parse_tc: rs_dcerpc_udp_parse,
+        get_tx_count: rs_dcerpc_udp_get_tx_cnt,
+        get_tx: rs_dcerpc_udp_get_tx,
+        tx_comp_st_ts: DCERPC_UDP_PROGRESS_DONE,
+        tx_comp_st_tc: DCERPC_UDP_PROGRESS_DONE,
+        tx_get_progress: rs_dcerpc_get_alstate_progress,
+        get_eventinfo: Some(DCERPCUDPEvent::get_event_info),
+        get_eventinfo_byid: Some(DCERPCUDPEvent::get_event_info_by_id),
+        localstorage_new: None,
+        localstorage_free: None,
+        get_tx_files: None,
+        get_tx_iterator: Some(applayer::state_get_tx_iterator::<DCERPCUDPState, DCERPCTransaction>),
+        get_tx_data: rs_dcerpc_udp_get_tx_data,
+        get_state_data: rs_dcerpc_udp_get_state_data,
+        apply_tx_config: None,
+        flags: 0,
+        truncate: None,
+        get_frame_id_by_name: None,
+        get_frame_name_by_id: None,
+    };
+
+    let ip_proto_str = CString::new("udp").unwrap();
+    if AppLayerProtoDetectConfProtoDetectionEnabled(ip_proto_str.as_ptr(), parser.name) != 0 {
+        let alproto = AppLayerRegisterProtocolDetection(&parser, 1);
+        ALPROTO_DCERPC = alproto;
+        if register_pattern_probe() < 0 {
+            return;
+        }
+        if AppLayerParserConfParserEnabled(ip_proto_str.as_ptr(), parser.name) != 0 {
+            let _ = AppLayerRegisterParser(&parser, alproto);
+        }
+        if let Some(val) = conf_get("app-layer.protocols.dcerpc.udp.max-frags") {
+            if let Ok(v) = val.parse::<u16>() {
+                DCERPC_UDP_MAX_FRAGS_CFG = v;
+            } else {
+                SCLogError!("Invalid value for dcerpc.udp.max-frags");
+            }
+        }
+        if let Some(val) = conf_get("app-layer.protocols.dcerpc.udp.stub-data-max") {
+            match get_memval(val) {
+                Ok(v) if v <= u32::MAX as u64 => {
+                    DCERPC_UDP_STUB_DATA_MAX_CFG = v as u32;
+                }
+                _ => {
+                    SCLogError!("Invalid value for dcerpc.udp.stub-data-max");
+                }
+            }
+        }
+        if let Some(val) = conf_get("app-layer.protocols.dcerpc.udp.flow-stub-budget") {
+            match get_memval(val) {
+                Ok(v) => {
+                    DCERPC_UDP_FLOW_STUB_BUDGET_CFG = v;
+                }
+                _ => {
+                    SCLogError!("Invalid value for dcerpc.udp.flow-stub-budget");
+                }
+            }
+        }
+        if let Some(val) = conf_get("app-layer.protocols.dcerpc.udp.max-opnum") {
+            if let Ok(v) = val.parse::<u16>() {
+                DCERPC_UDP_MAX_OPNUM_CFG = v;
+            } else {
+                SCLogError!("Invalid value for dcerpc.udp.max-opnum");
+            }
+        }
+        if let Some(val) = conf_get("app-layer.protocols.dcerpc.udp.max-tx") {
+            if let Ok(v) = val.parse::<usize>() {
+                DCERPC_UDP_MAX_TX_CFG = v;
+            } else {
+                SCLogError!("Invalid value for dcerpc.udp.max-tx");
+            }
+        }
+        DCERPC_UDP_REORDER_TOLERANCE_CFG =
+            conf_get_bool("app-layer.protocols.dcerpc.udp.reorder-tolerance");
+        DCERPC_UDP_METADATA_ONLY_CFG =
+            conf_get_bool("app-layer.protocols.dcerpc.udp.metadata-only");
+        if let Some(val) = conf_get("app-layer.protocols.dcerpc.udp.scan-threshold") {
+            if let Ok(v) = val.parse::<u32>() {
+                DCERPC_UDP_SCAN_THRESHOLD_CFG = v;
+            } else {
+                SCLogError!("Invalid value for dcerpc.udp.scan-threshold");
+            }
+        }
+        if let Some(val) = conf_get("app-layer.protocols.dcerpc.udp.large-datagram-threshold") {
+            if let Ok(v) = val.parse::<u32>() {
+                DCERPC_UDP_LARGE_DATAGRAM_THRESHOLD_CFG = v;
+            } else {
+                SCLogError!("Invalid value for dcerpc.udp.large-datagram-threshold");
+            }
+        }
+        if let Some(val) = conf_get("app-layer.protocols.dcerpc.udp.sensitive-interfaces") {
+            let mut sensitive_interfaces = Vec::new();
+            for uuid in val.split(',').map(str::trim).filter(|uuid| !uuid.is_empty()) {
+                match Uuid::parse_str(uuid) {
+                    Ok(uuid) => sensitive_interfaces.push(DCERPCUuid(*uuid.as_bytes())),
+                    Err(_) => {
+                        SCLogError!("Invalid UUID {} in dcerpc.udp.sensitive-interfaces", uuid);
+                    }
+                }
+            }
+            if let Ok(mut cfg) = DCERPC_UDP_SENSITIVE_INTERFACES_CFG.write() {
+                *cfg = sensitive_interfaces;
+            }
+        }
+        DCERPC_UDP_FRAGMENT_LOG_CFG = conf_get_bool("app-layer.protocols.dcerpc.udp.fragment-log");
+        DCERPC_UDP_STRICT_CFG = conf_get_bool("app-layer.protocols.dcerpc.udp.strict");
+        DCERPC_UDP_STRIP_STUB_PADDING_CFG =
+            conf_get_bool("app-layer.protocols.dcerpc.udp.strip-stub-padding");
+        if let Some(val) = conf_get("app-layer.protocols.dcerpc.udp.allowed-byte-orders") {
+            match val {
+                "any" => DCERPC_UDP_ALLOWED_BYTE_ORDERS_CFG = DCERPC_UDP_BYTE_ORDER_ANY,
+                "little" => DCERPC_UDP_ALLOWED_BYTE_ORDERS_CFG = DCERPC_UDP_BYTE_ORDER_LITTLE,
+                "big" => DCERPC_UDP_ALLOWED_BYTE_ORDERS_CFG = DCERPC_UDP_BYTE_ORDER_BIG,
+                _ => {
+                    SCLogError!("Invalid value for dcerpc.udp.allowed-byte-orders");
+                }
+            }
+        }
+    } else {
+        SCLogDebug!("Protocol detecter and parser disabled for DCERPC/UDP.");
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::applayer::AppLayerResult;
+    use crate::core::Direction;
+    use crate::dcerpc::dcerpc::{PFCL1_MAYBE, PFCL1_NOFACK};
+    use crate::dcerpc::dcerpc_udp::*;
+    use crate::dcerpc::parser;
+
+    const DCOM_REQUEST: &[u8] = &[
+        0x04, 0x00, 0x2c, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xa0, 0x01, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0x3f, 0x98,
+        0xf0, 0x5c, 0xd9, 0x63, 0xcc, 0x46, 0xc2, 0x74, 0x51, 0x6c, 0x8a, 0x53, 0x7d, 0x6f,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00,
+        0xff, 0xff, 0xff, 0xff, 0x70, 0x05, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x06, 0x00,
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x32, 0x24, 0x58, 0xfd, 0xcc, 0x45,
+        0x64, 0x49, 0xb0, 0x70, 0xdd, 0xae, 0x74, 0x2c, 0x96, 0xd2, 0x60, 0x5e, 0x0d, 0x00,
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x70, 0x5e, 0x0d, 0x00, 0x02, 0x00,
+        0x00, 0x00, 0x7c, 0x5e, 0x0d, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00,
+        0x80, 0x96, 0xf1, 0xf1, 0x2a, 0x4d, 0xce, 0x11, 0xa6, 0x6a, 0x00, 0x20, 0xaf, 0x6e,
+        0x72, 0xf4, 0x0c, 0x00, 0x00, 0x00, 0x4d, 0x41, 0x52, 0x42, 0x01, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x0d, 0xf0, 0xad, 0xba, 0x00, 0x00, 0x00, 0x00, 0xa8, 0xf4,
+        0x0b, 0x00, 0x10, 0x09, 0x00, 0x00, 0x10, 0x09, 0x00, 0x00, 0x4d, 0x45, 0x4f, 0x57,
+        0x04, 0x00, 0x00, 0x00, 0xa2, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0x38, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0x00, 0x00, 0x00, 0x00, 0xe0, 0x08,
+        0x00, 0x00, 0xd8, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x10, 0x08, 0x00,
+        0xcc, 0xcc, 0xcc, 0xcc, 0xc8, 0x00, 0x00, 0x00, 0x4d, 0x45, 0x4f, 0x57, 0xd8, 0x08,
+        0x00, 0x00, 0xd8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+        0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc4, 0x28, 0xcd, 0x00, 0x64, 0x29, 0xcd, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0xb9, 0x01, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0xab, 0x01, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0xa5, 0x01,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+        0xa6, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x46, 0xa4, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x46, 0xad, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0xaa, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0x07, 0x00, 0x00, 0x00, 0x60, 0x00,
+        0x00, 0x00, 0x58, 0x00, 0x00, 0x00, 0x90, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00,
+        0x20, 0x00, 0x00, 0x00, 0x28, 0x06, 0x00, 0x00, 0x30, 0x00, 0x00, 0x00, 0x01, 0x00,
+        0x00, 0x00, 0x01, 0x10, 0x08, 0x00, 0xcc, 0xcc, 0xcc, 0xcc, 0x50, 0x00, 0x00, 0x00,
+        0x4f, 0xb6, 0x88, 0x20, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x01, 0x10, 0x08, 0x00, 0xcc, 0xcc, 0xcc, 0xcc, 0x48, 0x00, 0x00, 0x00, 0x07, 0x00,
+        0x66, 0x00, 0x06, 0x09, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x46, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x78, 0x19, 0x0c, 0x00,
+        0x58, 0x00, 0x00, 0x00, 0x05, 0x00, 0x06, 0x00, 0x01, 0x00, 0x00, 0x00, 0x70, 0xd8,
+        0x98, 0x93, 0x98, 0x4f, 0xd2, 0x11, 0xa9, 0x3d, 0xbe, 0x57, 0xb2, 0x00, 0x00, 0x00,
+        0x32, 0x00, 0x31, 0x00, 0x01, 0x10, 0x08, 0x00, 0xcc, 0xcc, 0xcc, 0xcc, 0x80, 0x00,
+        0x00, 0x00, 0x0d, 0xf0, 0xad, 0xba, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x43, 0x14, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x60, 0x00, 0x00, 0x00, 0x60, 0x00, 0x00, 0x00, 0x4d, 0x45, 0x4f, 0x57,
+        0x04, 0x00, 0x00, 0x00, 0xc0, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0x3b, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0x00, 0x00, 0x00, 0x00, 0x30, 0x00,
+        0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x81, 0xc5, 0x17, 0x03, 0x80, 0x0e, 0xe9, 0x4a,
+        0x99, 0x99, 0xf1, 0x8a, 0x50, 0x6f, 0x7a, 0x85, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x10, 0x08, 0x00, 0xcc, 0xcc,
+        0xcc, 0xcc, 0x30, 0x00, 0x00, 0x00, 0x78, 0x00, 0x6e, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0xd8, 0xda, 0x0d, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x2f,
+        0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x46, 0x00, 0x58, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x01, 0x10, 0x08, 0x00, 0xcc, 0xcc, 0xcc, 0xcc, 0x10, 0x00, 0x00, 0x00,
+        0x30, 0x00, 0x2e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x10, 0x08, 0x00, 0xcc, 0xcc, 0xcc, 0xcc,
+        0x68, 0x00, 0x00, 0x00, 0x0e, 0x00, 0xff, 0xff, 0x68, 0x8b, 0x0b, 0x00, 0x02, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfe, 0x02, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0xfe, 0x02, 0x00, 0x00, 0x5c, 0x00, 0x5c, 0x00, 0x31, 0x00,
+        0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x31, 0x00,
+        0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x31, 0x00,
+        0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x9d, 0x13, 0x00, 0x01, 0xcc, 0xe0, 0xfd, 0x7f,
+        0xcc, 0xe0, 0xfd, 0x7f, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
+        0x90, 0x90,
+    ];
+
+    /// The request fixture as fragment `fragnum` of the same call.
+    fn dcom_fragment(fragnum: u16) -> Vec<u8> {
+        let mut request = DCOM_REQUEST.to_vec();
+        request[76..78].copy_from_slice(&fragnum.to_le_bytes());
+        request
+    }
+
+    /// Builds a serialized connectionless header for tests.
+    struct DCERPCUdpHeaderBuilder {
+        pkt_type: u8,
+        flags1: u8,
+        little_endian: bool,
+        serial: u16,
+        interface_uuid: [u8; 16],
+        activity_uuid: [u8; 16],
+        server_boot: u32,
+        seqnum: u32,
+        opnum: u16,
+        ihint: u16,
+        ahint: u16,
+        fraglen: u16,
+        fragnum: u16,
+        auth_proto: u8,
+    }
+
+    impl DCERPCUdpHeaderBuilder {
+        fn new() -> Self {
+            Self {
+                pkt_type: DCERPC_TYPE_REQUEST,
+                flags1: 0x08,
+                little_endian: true,
+                serial: 0,
+                interface_uuid: [0; 16],
+                activity_uuid: [0; 16],
+                server_boot: 0,
+                seqnum: 0,
+                opnum: 0,
+                ihint: 0xffff,
+                ahint: 0xffff,
+                fraglen: 0,
+                fragnum: 0,
+                auth_proto: 0,
+            }
+        }
+
+        fn pkt_type(mut self, pkt_type: DCERPCPktType) -> Self {
+            self.pkt_type = pkt_type as u8;
+            self
+        }
+
+        fn flags1(mut self, flags1: u8) -> Self {
+            self.flags1 = flags1;
+            self
+        }
+
+        fn drep_byte_order(mut self, little_endian: bool) -> Self {
+            self.little_endian = little_endian;
+            self
+        }
+
+        fn serial(mut self, serial: u16) -> Self {
+            self.serial = serial;
+            self
+        }
+
+        fn interface_uuid(mut self, uuid: [u8; 16]) -> Self {
+            self.interface_uuid = uuid;
+            self
+        }
+
+        fn activity_uuid(mut self, uuid: [u8; 16]) -> Self {
+            self.activity_uuid = uuid;
+            self
+        }
+
+        fn server_boot(mut self, server_boot: u32) -> Self {
+            self.server_boot = server_boot;
+            self
+        }
+
+        fn seqnum(mut self, seqnum: u32) -> Self {
+            self.seqnum = seqnum;
+            self
+        }
+
+        fn opnum(mut self, opnum: u16) -> Self {
+            self.opnum = opnum;
+            self
+        }
+
+        fn ihint(mut self, ihint: u16) -> Self {
+            self.ihint = ihint;
+            self
+        }
+
+        fn ahint(mut self, ahint: u16) -> Self {
+            self.ahint = ahint;
+            self
+        }
+
+        fn fraglen(mut self, fraglen: u16) -> Self {
+            self.fraglen = fraglen;
+            self
+        }
+
+        fn fragnum(mut self, fragnum: u16) -> Self {
+            self.fragnum = fragnum;
+            self
+        }
+
+        fn auth_proto(mut self, auth_proto: u8) -> Self {
+            self.auth_proto = auth_proto;
+            self
+        }
+
+        fn build(&self) -> Vec<u8> {
+            let le = self.little_endian;
+            let u16b = |v: u16| if le { v.to_le_bytes() } else { v.to_be_bytes() };
+            let u32b = |v: u32| if le { v.to_le_bytes() } else { v.to_be_bytes() };
+            let mut hdr = Vec::with_capacity(DCERPC_UDP_HDR_LEN as usize);
+            hdr.extend_from_slice(&[4, self.pkt_type, self.flags1, 0]);
+            hdr.extend_from_slice(&[if le { 0x10 } else { 0x00 }, 0, 0]);
+            hdr.push((self.serial >> 8) as u8);
+            hdr.extend_from_slice(&[0; 16]);
+            hdr.extend_from_slice(&self.interface_uuid);
+            hdr.extend_from_slice(&self.activity_uuid);
+            hdr.extend_from_slice(&u32b(self.server_boot));
+            hdr.extend_from_slice(&u32b(0)); // if_vers
+            hdr.extend_from_slice(&u32b(self.seqnum));
+            hdr.extend_from_slice(&u16b(self.opnum));
+            hdr.extend_from_slice(&u16b(self.ihint));
+            hdr.extend_from_slice(&u16b(self.ahint));
+            hdr.extend_from_slice(&u16b(self.fraglen));
+            hdr.extend_from_slice(&u16b(self.fragnum));
+            hdr.push(self.auth_proto);
+            hdr.push(self.serial as u8);
+            hdr
+        }
+    }
+
+    #[test]
+    fn test_process_header_udp_incomplete_hdr() {
+        let request: &[u8] = &[
+            0x04, 0x00, 0x08, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xb8, 0x4a, 0x9f, 0x4d,
+            0x1c, 0x7d, 0xcf, 0x11,
+        ];
+
+        match parser::parse_dcerpc_udp_header(request) {
+            Ok((_rem, _header)) => {
+                { assert!(false); }
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_process_header_udp_perfect_hdr() {
+        let request: &[u8] = &[
+            0x04, 0x00, 0x08, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xb8, 0x4a, 0x9f, 0x4d,
+            0x1c, 0x7d, 0xcf, 0x11, 0x86, 0x1e, 0x00, 0x20, 0xaf, 0x6e, 0x7c, 0x57, 0x86, 0xc2,
+            0x37, 0x67, 0xf7, 0x1e, 0xd1, 0x11, 0xbc, 0xd9, 0x00, 0x60, 0x97, 0x92, 0xd2, 0x6c,
+            0x79, 0xbe, 0x01, 0x34, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0xff, 0xff, 0xff, 0xff, 0x68, 0x00, 0x00, 0x00, 0x0a, 0x00,
+        ];
+        match parser::parse_dcerpc_udp_header(request) {
+            Ok((rem, header)) => {
+                assert_eq!(4, header.rpc_vers);
+                assert_eq!(80, request.len() - rem.len());
+            }
+            _ => { assert!(false); }
+        }
+    }
+
+    #[test]
+    fn test_handle_fragment_data_udp_no_body() {
+        let request = DCERPCUdpHeaderBuilder::new()
+            .fraglen(0x68)
+            .auth_proto(0x0a)
+            .build();
+        let request = request.as_slice();
+        match parser::parse_dcerpc_udp_header(request) {
+            Ok((rem, header)) => {
+                assert_eq!(4, header.rpc_vers);
+                assert_eq!(80, request.len() - rem.len());
+                assert_eq!(0, rem.len());
+            }
+            _ => { assert!(false); }
+        }
+    }
+
+    #[test]
+    fn test_handle_input_data_udp_full_body() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        assert_eq!(
+            1392,
+            dcerpcudp_state.transactions[0].stub_data_buffer_ts.len()
+        );
+    }
+
+    #[test]
+    fn test_handle_input_data_udp_split_hdr() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::incomplete(40, 40),
+            dcerpcudp_state.handle_input_data(&DCOM_REQUEST[..40])
+        );
+        assert_eq!(0, dcerpcudp_state.transactions.len());
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&DCOM_REQUEST[40..])
+        );
+        assert!(dcerpcudp_state.buffer.is_empty());
+        assert_eq!(
+            1392,
+            dcerpcudp_state.transactions[0].stub_data_buffer_ts.len()
+        );
+    }
+
+    #[test]
+    fn test_pkt_type_from_u8() {
+        for t in 0..=10 {
+            let pkt_type = DCERPCPktType::from_u8(t).unwrap();
+            assert_eq!(t, pkt_type as u8);
+        }
+        assert_eq!(Some(DCERPCPktType::Fack), DCERPCPktType::from_u8(9));
+        assert_eq!(None, DCERPCPktType::from_u8(11));
+        assert_eq!(None, DCERPCPktType::from_u8(0xff));
+        assert_eq!("request", DCERPCPktType::Request.as_str());
+        assert_eq!("cancel_ack", DCERPCPktType::CancelAck.as_str());
+    }
+
+    #[test]
+    fn test_handle_input_data_udp_stub_capacity() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        let stub = &dcerpcudp_state.transactions[0].stub_data_buffer_ts;
+        assert_eq!(1392, stub.len());
+        // reserved once from fraglen, not grown by doubling
+        assert!(stub.capacity() >= stub.len());
+        assert!(stub.capacity() < 2 * stub.len());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_roundtrip() {
+        use crate::dcerpc::dcerpc::DCERPCTransaction;
+        use crate::dcerpc::dcerpc_udp::DCERPCHdrUdp;
+
+        let (_, header) = parser::parse_dcerpc_udp_header(DCOM_REQUEST).unwrap();
+        let json = header.to_json().unwrap();
+        let header2: DCERPCHdrUdp = serde_json::from_str(&json).unwrap();
+        assert_eq!(header.activityuuid, header2.activityuuid);
+        assert_eq!(header.fraglen, header2.fraglen);
+        assert_eq!(json, header2.to_json().unwrap());
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        let tx = &dcerpcudp_state.transactions[0];
+        let json = serde_json::to_string(tx).unwrap();
+        let tx2: DCERPCTransaction = serde_json::from_str(&json).unwrap();
+        assert_eq!(tx.stub_data_buffer_ts, tx2.stub_data_buffer_ts);
+        assert_eq!(tx.seqnum, tx2.seqnum);
+        assert_eq!(tx.req_done, tx2.req_done);
+    }
+
+    #[test]
+    fn test_handle_input_data_udp_max_frags() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.max_frags = 2;
+        for fragnum in 0..3 {
+            assert_eq!(
+                AppLayerResult::ok(),
+                dcerpcudp_state.handle_input_data(&dcom_fragment(fragnum))
+            );
+        }
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(3, unsafe { rs_dcerpc_udp_get_tx_frag_cnt(tx, Direction::ToServer.into()) });
+        assert_eq!(0, unsafe { rs_dcerpc_udp_get_tx_frag_cnt(tx, Direction::ToClient.into()) });
+        assert_eq!(2 * 1392, tx.stub_data_buffer_ts.len());
+        assert_eq!(1, dcerpcudp_state.events);
+
+        // the fragment count saturates at the largest limit
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.max_frags = u16::MAX;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&dcom_fragment(0)));
+        dcerpcudp_state.transactions[0].frag_cnt_ts = u16::MAX - 1;
+        for fragnum in 1..3 {
+            assert_eq!(
+                AppLayerResult::ok(),
+                dcerpcudp_state.handle_input_data(&dcom_fragment(fragnum))
+            );
+        }
+        assert_eq!(u16::MAX, dcerpcudp_state.transactions[0].frag_cnt_ts);
+        assert_eq!(0, dcerpcudp_state.events);
+    }
+
+    #[test]
+    fn test_handle_input_data_udp_auth_verifier() {
+        let mut request = DCOM_REQUEST.to_vec();
+        request[78] = 0x0a; // auth_proto: NTLMSSP
+        request.extend_from_slice(&[0x01, 0x00, 0x00, 0x00, 0xaa, 0xbb, 0xcc, 0xdd,
+                                    0xaa, 0xbb, 0xcc, 0xdd, 0x00, 0x00, 0x00, 0x00]);
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&request)
+        );
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(1392, tx.stub_data_buffer_ts.len());
+        assert_eq!(&DCOM_REQUEST[80..], tx.stub_data_buffer_ts.as_slice());
+        assert_eq!(0x0a, rs_dcerpc_udp_get_tx_auth_type(tx));
+        assert_eq!(16, rs_dcerpc_udp_get_tx_auth_len(tx));
+    }
+
+    #[test]
+    fn test_process_header_udp_builder_big_endian() {
+        let request = DCERPCUdpHeaderBuilder::new()
+            .pkt_type(DCERPCPktType::Response)
+            .flags1(PFCL1_LASTFRAG)
+            .drep_byte_order(false)
+            .serial(0x0102)
+            .interface_uuid([0x11; 16])
+            .activity_uuid([0x22; 16])
+            .seqnum(7)
+            .opnum(4)
+            .fraglen(0x100)
+            .fragnum(3)
+            .build();
+        assert_eq!(DCERPC_UDP_HDR_LEN as usize, request.len());
+        let (rem, header) = parser::parse_dcerpc_udp_header(&request).unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(Some(DCERPCPktType::Response), header.get_hdr_pkt_type());
+        assert_eq!(0, header.drep[0]);
+        assert_eq!(0x01, header.serial_hi);
+        assert_eq!(0x02, header.serial_lo);
+        assert_eq!(7, header.seqnum);
+        assert_eq!(4, header.opnum);
+        assert_eq!(0x100, header.fraglen);
+        assert_eq!(3, header.fragnum);
+        assert_eq!(DCERPCUuid([0x22; 16]), header.activityuuid);
+    }
+
+    #[test]
+    fn test_handle_input_data_udp_stub_too_large() {
+        // more stub data than fits in a u16
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        for fragnum in 0..50 {
+            assert_eq!(
+                AppLayerResult::ok(),
+                dcerpcudp_state.handle_input_data(&dcom_fragment(fragnum))
+            );
+        }
+        assert_eq!(50 * 1392, dcerpcudp_state.transactions[0].stub_data_buffer_ts.len());
+        assert_eq!(0, dcerpcudp_state.events);
+
+        // past the cap appends stop and the event is set once
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.stub_data_max = 2000;
+        for fragnum in 0..3 {
+            assert_eq!(
+                AppLayerResult::ok(),
+                dcerpcudp_state.handle_input_data(&dcom_fragment(fragnum))
+            );
+        }
+        let tx = &dcerpcudp_state.transactions[0];
+        assert_eq!(2000, tx.stub_data_buffer_ts.len());
+        assert!(tx.udp().stub_data_truncated_ts);
+        assert_eq!(1, dcerpcudp_state.events);
+    }
+
+    #[test]
+    fn test_parse_udp_gap_resets_partial_state() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let state = &mut dcerpcudp_state as *mut DCERPCUDPState as *mut std::os::raw::c_void;
+        let parse = |input: &[u8], flags: u8| unsafe {
+            rs_dcerpc_udp_parse(
+                std::ptr::null(), state, std::ptr::null_mut(),
+                StreamSlice::from_slice(input, flags, 0), std::ptr::null(),
+            )
+        };
+
+        // first fragment of a request, then a partial header
+        assert_eq!(AppLayerResult::ok(), parse(DCOM_REQUEST, core::STREAM_TOSERVER));
+        let _ = parse(&DCOM_REQUEST[..40], core::STREAM_TOSERVER);
+        assert_eq!(40, dcerpcudp_state.buffer.len());
+
+        // after the gap the datagram is parsed on its own in a new tx
+        assert_eq!(
+            AppLayerResult::ok(),
+            parse(DCOM_REQUEST, core::STREAM_TOSERVER | core::STREAM_GAP)
+        );
+        assert!(dcerpcudp_state.buffer.is_empty());
+        assert_eq!(2, dcerpcudp_state.transactions.len());
+        let tx = &dcerpcudp_state.transactions[0];
+        assert!(tx.req_done && tx.req_lost);
+        assert_eq!(1392, tx.stub_data_buffer_ts.len());
+        let tx = &dcerpcudp_state.transactions[1];
+        assert!(!tx.req_done);
+        assert_eq!(1392, tx.stub_data_buffer_ts.len());
+
+        // EOF finalizes the open transaction
+        assert_eq!(AppLayerResult::ok(), parse(&[], core::STREAM_TOSERVER | core::STREAM_EOF));
+        let tx = &dcerpcudp_state.transactions[1];
+        assert!(tx.req_done && tx.req_lost);
+        assert!(tx.resp_done && !tx.resp_lost);
+    }
+
+    #[test]
+    fn test_tx_status() {
+        let mut request = DCOM_REQUEST.to_vec();
+        request[2] |= PFCL1_LASTFRAG;
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&request)
+        );
+        let status = dcerpcudp_state.tx_status(0).unwrap();
+        assert!(status.req_done);
+        assert!(!status.resp_done);
+        assert_eq!(1, status.frag_cnt_ts);
+        assert_eq!(0, status.frag_cnt_tc);
+        assert_eq!(None, dcerpcudp_state.tx_status(1));
+
+        let state = &mut dcerpcudp_state as *mut DCERPCUDPState as *mut std::os::raw::c_void;
+        let mut c_status = DCERPCUDPTxStatus::default();
+        assert_eq!(1, unsafe { rs_dcerpc_udp_get_tx_status(state, 0, &mut c_status) });
+        assert_eq!(1, c_status.req_done);
+        assert_eq!(0, c_status.resp_done);
+        assert_eq!(0, unsafe { rs_dcerpc_udp_get_tx_status(state, 1, &mut c_status) });
+    }
+
+    #[test]
+    fn test_validate_flags() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        assert_eq!(0, dcerpcudp_state.events);
+
+        // reserved flags2 bit
+        let mut request = DCOM_REQUEST.to_vec();
+        request[3] = PFCL2_RESERVED_40;
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&request)
+        );
+        assert_eq!(1, dcerpcudp_state.events);
+        assert_eq!(1392, dcerpcudp_state.transactions[0].stub_data_buffer_ts.len());
+
+        // broadcast without idempotent
+        let mut request = DCOM_REQUEST.to_vec();
+        request[2] = (request[2] & !PFCL1_IDEMPOTENT) | PFCL1_BROADCAST;
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&request)
+        );
+        assert_eq!(1, dcerpcudp_state.events);
+
+        // broadcast and idempotent is fine
+        let mut request = DCOM_REQUEST.to_vec();
+        request[2] |= PFCL1_BROADCAST | PFCL1_IDEMPOTENT;
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&request)
+        );
+        assert_eq!(0, dcerpcudp_state.events);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        for seqnum in 0..4 {
+            let mut request = DCOM_REQUEST.to_vec();
+            request[2] |= PFCL1_LASTFRAG;
+            request[64] = seqnum;
+            assert_eq!(
+                AppLayerResult::ok(),
+                dcerpcudp_state.handle_input_data(&request)
+            );
+        }
+        let _ = dcerpcudp_state.handle_input_data(&DCOM_REQUEST[..40]);
+        assert_eq!(4, dcerpcudp_state.transactions.len());
+        let capacity = dcerpcudp_state.transactions.capacity();
+
+        dcerpcudp_state.reset();
+        assert!(dcerpcudp_state.transactions.capacity() >= capacity);
+        assert_eq!(
+            format!("{:?}", DCERPCUDPState::new()),
+            format!("{:?}", dcerpcudp_state)
+        );
+
+        let mut fresh_state = DCERPCUDPState::new();
+        assert_eq!(
+            fresh_state.handle_input_data(DCOM_REQUEST),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        assert_eq!(format!("{:?}", fresh_state), format!("{:?}", dcerpcudp_state));
+    }
+
+    #[test]
+    fn test_handle_input_data_udp_short_body() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::incomplete(1000, DCOM_REQUEST.len() as u32 - 1000),
+            dcerpcudp_state.handle_input_data(&DCOM_REQUEST[..1000])
+        );
+        assert!(dcerpcudp_state.transactions.is_empty());
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&DCOM_REQUEST[1000..])
+        );
+        assert!(dcerpcudp_state.buffer.is_empty());
+        assert_eq!(&DCOM_REQUEST[80..], dcerpcudp_state.transactions[0].stub_data_buffer_ts.as_slice());
+    }
+
+    #[test]
+    fn test_stub_sha256() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        let tx = &mut dcerpcudp_state.transactions[0];
+        let mut hash = [0u8; 32];
+        unsafe {
+            rs_dcerpc_udp_get_tx_stub_sha256(tx, Direction::ToServer.into(), hash.as_mut_ptr());
+        }
+        assert_eq!(
+            "3a5c5a86b78b215eeb8d5a09db8640138aa70b3df735221e0af0560d9c686e89",
+            hex::encode(hash)
+        );
+        assert_eq!(Some(hash), tx.udp().stub_sha256_ts);
+        assert_eq!(None, tx.udp().stub_sha256_tc);
+
+        // more stub data invalidates the cached hash
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&dcom_fragment(1))
+        );
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(None, tx.udp().stub_sha256_ts);
+        assert_ne!(hash, tx.stub_sha256(Direction::ToServer));
+    }
+
+    #[test]
+    fn test_response_without_request() {
+        let mut response = DCOM_REQUEST.to_vec();
+        response[1] = DCERPC_TYPE_RESPONSE;
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&response)
+        );
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        assert_eq!(1, dcerpcudp_state.events);
+        let tx = &dcerpcudp_state.transactions[0];
+        assert!(tx.stub_data_buffer_ts.is_empty());
+        assert_eq!(1392, tx.stub_data_buffer_tc.len());
+
+        // a response to a request seen before does not set the event
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&response)
+        );
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        assert_eq!(0, dcerpcudp_state.events);
+    }
+
+    #[test]
+    fn test_empty_drep() {
+        let hdr = DCERPCHdrUdp {
+            rpc_vers: 4,
+            pkt_type: DCERPC_TYPE_REQUEST,
+            ..Default::default()
+        };
+        assert!(hdr.drep.is_empty());
+        assert_eq!(Err(HeaderError::MissingDrep), hdr.get_hdr_drep_0());
+        assert_eq!(Err(HeaderError::MissingDrep), hdr.get_hdr_drep_1());
+
+        // the PDU is rejected rather than assumed to be big endian
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert!(!dcerpcudp_state.handle_fragment_data(&hdr, &[]));
+        assert!(dcerpcudp_state.transactions.is_empty());
+
+        let (_, hdr) = parser::parse_dcerpc_udp_header(DCOM_REQUEST).unwrap();
+        assert_eq!(Ok(0x10), hdr.get_hdr_drep_0());
+        assert_eq!(Ok(0x00), hdr.get_hdr_drep_1());
+    }
+
+    #[test]
+    fn test_operation_name() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        let tx = &dcerpcudp_state.transactions[0];
+        assert_eq!(4, tx.get_req_opnum());
+        assert_eq!(Some("RemoteCreateInstance"), tx.get_operation_name());
+    }
+
+    #[test]
+    fn test_match_frag() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(0, rs_dcerpc_udp_get_tx_fragnum(tx));
+        assert_eq!(1, rs_dcerpc_udp_tx_match_frag(tx, DCERPC_UDP_FRAG_FIRST));
+        assert_eq!(0, rs_dcerpc_udp_tx_match_frag(tx, DCERPC_UDP_FRAG_LAST));
+        assert_eq!(0, rs_dcerpc_udp_tx_match_frag(tx, DCERPC_UDP_FRAG_FIRST | DCERPC_UDP_FRAG_LAST));
+
+        // middle fragment
+        let mut request = DCOM_REQUEST.to_vec();
+        request[76] = 0x01;
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&request)
+        );
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(1, rs_dcerpc_udp_get_tx_fragnum(tx));
+        assert_eq!(0, rs_dcerpc_udp_tx_match_frag(tx, DCERPC_UDP_FRAG_FIRST));
+        assert_eq!(0, rs_dcerpc_udp_tx_match_frag(tx, DCERPC_UDP_FRAG_LAST));
+        assert_eq!(1, rs_dcerpc_udp_tx_match_frag(tx, 0));
+
+        // last fragment
+        request[2] |= PFCL1_LASTFRAG;
+        request[76] = 0x02;
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&request)
+        );
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(2, rs_dcerpc_udp_get_tx_fragnum(tx));
+        assert_eq!(1, rs_dcerpc_udp_tx_match_frag(tx, DCERPC_UDP_FRAG_LAST));
+    }
+
+    #[test]
+    fn test_handle_input_data_udp_multiple_pdus() {
+        let mut second = DCOM_REQUEST.to_vec();
+        second[64] = 0x01; // seqnum
+        let mut request = DCOM_REQUEST.to_vec();
+        request.extend_from_slice(&second);
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&request)
+        );
+        assert_eq!(2, dcerpcudp_state.transactions.len());
+        assert_eq!(0, dcerpcudp_state.transactions[0].seqnum);
+        assert_eq!(1, dcerpcudp_state.transactions[1].seqnum);
+        for tx in &dcerpcudp_state.transactions {
+            assert_eq!(&DCOM_REQUEST[80..], tx.stub_data_buffer_ts.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_tx_hints() {
+        for little_endian in [true, false] {
+            let request = DCERPCUdpHeaderBuilder::new()
+                .drep_byte_order(little_endian)
+                .ihint(0x0102)
+                .ahint(0x0304)
+                .build();
+            let mut dcerpcudp_state = DCERPCUDPState::new();
+            assert_eq!(
+                AppLayerResult::ok(),
+                dcerpcudp_state.handle_input_data(&request)
+            );
+            let tx = &mut dcerpcudp_state.transactions[0];
+            let (mut ihint, mut ahint) = (0, 0);
+            unsafe { rs_dcerpc_udp_get_tx_hints(tx, &mut ihint, &mut ahint) };
+            assert_eq!(0x0102, ihint);
+            assert_eq!(0x0304, ahint);
+        }
+    }
+
+    #[test]
+    fn test_evict_stale() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        for seqnum in 0..4 {
+            let mut request = DCOM_REQUEST.to_vec();
+            request[64] = seqnum;
+            assert_eq!(
+                AppLayerResult::ok(),
+                dcerpcudp_state.handle_input_data(&request)
+            );
+        }
+        // refresh the first transaction
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        assert_eq!(4, dcerpcudp_state.transactions.len());
+        assert_eq!(0, dcerpcudp_state.evict_stale(4));
+
+        assert_eq!(2, dcerpcudp_state.evict_stale(1));
+        let seqnums: Vec<u32> = dcerpcudp_state.transactions.iter().map(|tx| tx.seqnum).collect();
+        assert_eq!(vec![0, 3], seqnums);
+
+        let state = &mut dcerpcudp_state as *mut DCERPCUDPState as *mut std::os::raw::c_void;
+        assert_eq!(1, unsafe { rs_dcerpc_udp_evict_stale(state, 0) });
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        assert_eq!(0, dcerpcudp_state.transactions[0].seqnum);
+    }
+
+    #[test]
+    fn test_object_uuid() {
+        let mut buf: *const u8 = std::ptr::null();
+        let mut len = 0;
+
+        // the fixture has a nil object UUID
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(0, unsafe { rs_dcerpc_udp_get_tx_object_uuid(tx, &mut buf, &mut len) });
+        assert_eq!(0, len);
+
+        let mut request = DCOM_REQUEST.to_vec();
+        for (i, b) in request[8..24].iter_mut().enumerate() {
+            *b = i as u8 + 1;
+        }
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&request)
+        );
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(1, unsafe { rs_dcerpc_udp_get_tx_object_uuid(tx, &mut buf, &mut len) });
+        let uuid = unsafe { std::slice::from_raw_parts(buf, len as usize) };
+        assert_eq!(
+            &[0x04, 0x03, 0x02, 0x01, 0x06, 0x05, 0x08, 0x07,
+              0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10],
+            uuid
+        );
+    }
+
+    #[test]
+    fn test_serial_mismatch_during_reassembly() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        assert_eq!(0, dcerpcudp_state.events);
+        assert_eq!(0, dcerpcudp_state.transactions[0].call_id);
+
+        // a fragment of the same call with another serial number
+        let mut request = DCOM_REQUEST.to_vec();
+        request[79] = 0x01; // serial_lo
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&request)
+        );
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        assert_eq!(1, dcerpcudp_state.events);
+    }
+
+    #[test]
+    fn test_tx_summary() {
+        let mut request = DCOM_REQUEST.to_vec();
+        request[79] = 0x2a; // serial_lo
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&request)
+        );
+        let summary = dcerpcudp_state.transactions[0].summary();
+        assert!(summary.contains("call_id 42"));
+        assert!(summary.contains("opnum 4"));
+        assert!(summary.contains("stub_data_size 1392/0"));
+        // no stub bytes, in either representation
+        assert!(!summary.contains("MEOW"));
+        assert!(!summary.contains("[5, 0, 6, 0"));
+        assert!(summary.len() < 200);
+    }
+
+    #[test]
+    fn test_stub_ascii_ebcdic() {
+        // "HELLO" in EBCDIC
+        let stub = [0xc8, 0xc5, 0xd3, 0xd3, 0xd6];
+        let mut request = DCERPCUdpHeaderBuilder::new()
+            .flags1(0)
+            .fraglen(stub.len() as u16)
+            .build();
+        request[4] |= DCERPC_DREP_CHAR_EBCDIC;
+        request.extend_from_slice(&stub);
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&request)
+        );
+        let tx = &mut dcerpcudp_state.transactions[0];
+        let mut buf: *const u8 = std::ptr::null();
+        let mut len = 0;
+        assert_eq!(1, unsafe {
+            rs_dcerpc_udp_get_stub_data_ascii(tx, &mut buf, &mut len, Direction::ToServer.into())
+        });
+        assert_eq!(b"HELLO", unsafe { std::slice::from_raw_parts(buf, len as usize) });
+        // the raw buffer is left as is
+        assert_eq!(&stub, tx.stub_data_buffer_ts.as_slice());
+
+        // ASCII transactions have no normalized buffer
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(DCERPC_DREP_CHAR_ASCII, tx.udp().charset);
+        assert_eq!(None, tx.stub_ascii(Direction::ToServer));
+    }
+
+    #[test]
+    fn test_reassembly_length() {
+        let mut last = DCOM_REQUEST.to_vec();
+        last[2] |= PFCL1_LASTFRAG;
+        last[76] = 0x01; // fragnum
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(DCOM_REQUEST));
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&last));
+        let tx = &dcerpcudp_state.transactions[0];
+        assert!(tx.req_done);
+        assert_eq!(2 * 1392, tx.udp().stub_data_expected_len_ts);
+        assert_eq!(0, dcerpcudp_state.events);
+
+        // the second fragment is not reassembled
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.max_frags = 1;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(DCOM_REQUEST));
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&last));
+        let tx = &dcerpcudp_state.transactions[0];
+        assert!(tx.req_done);
+        assert_eq!(1392, tx.stub_data_buffer_ts.len());
+        // TooManyFragments and ReassemblyLengthMismatch
+        assert_eq!(2, dcerpcudp_state.events);
+    }
+
+    #[test]
+    fn test_has_pending() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let state = &mut dcerpcudp_state as *mut DCERPCUDPState as *mut std::os::raw::c_void;
+        assert_eq!(0, unsafe { rs_dcerpc_udp_has_pending(state) });
+
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(DCOM_REQUEST));
+        assert_eq!(1, unsafe { rs_dcerpc_udp_has_pending(state) });
+
+        let mut last = DCOM_REQUEST.to_vec();
+        last[2] |= PFCL1_LASTFRAG;
+        last[76] = 0x01; // fragnum
+        let _ = dcerpcudp_state.handle_input_data(&last[..40]);
+        assert!(dcerpcudp_state.has_pending());
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&last[40..]));
+        assert_eq!(0, unsafe { rs_dcerpc_udp_has_pending(state) });
+    }
+
+    #[test]
+    fn test_suspicious_opnum() {
+        let opnum_events = |iface: Option<&[u8]>, opnum: u16| {
+            let mut request = DCOM_REQUEST.to_vec();
+            if let Some(iface) = iface {
+                request[24..40].copy_from_slice(iface);
+            }
+            request[68..70].copy_from_slice(&opnum.to_le_bytes());
+            let mut dcerpcudp_state = DCERPCUDPState::new();
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+            // later fragments are not checked again
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+            dcerpcudp_state.events
+        };
+
+        // ISystemActivator has 5 operations
+        assert_eq!(0, opnum_events(None, 0));
+        assert_eq!(0, opnum_events(None, 4));
+        assert_eq!(1, opnum_events(None, 5));
+        assert_eq!(1, opnum_events(None, 0xffff));
+
+        // unknown interface, checked against max-opnum
+        let iface = [0x11; 16];
+        assert_eq!(0, opnum_events(Some(&iface), 0));
+        assert_eq!(0, opnum_events(Some(&iface), 100));
+        assert_eq!(0, opnum_events(Some(&iface), DCERPC_UDP_MAX_OPNUM));
+        assert_eq!(1, opnum_events(Some(&iface), 0xffff));
+    }
+
+    #[test]
+    fn test_reuse_tx_for_call() {
+        // two datagrams of the same call
+        let mut last = DCOM_REQUEST.to_vec();
+        last[2] |= PFCL1_LASTFRAG;
+        last[76] = 0x01; // fragnum
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(DCOM_REQUEST));
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&last));
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+
+        // retransmission of the complete request
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&last));
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        let tx = &dcerpcudp_state.transactions[0];
+        assert_eq!(2, tx.frag_cnt_ts);
+        assert_eq!(2 * 1392, tx.stub_data_buffer_ts.len());
+    }
+
+    #[test]
+    fn test_first_frag_resets_stub_data() {
+        let mut second = DCOM_REQUEST.to_vec();
+        second[80..].iter_mut().for_each(|b| *b = 0x41);
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(DCOM_REQUEST));
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&second));
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        let tx = &dcerpcudp_state.transactions[0];
+        assert_eq!(&second[80..], tx.stub_data_buffer_ts.as_slice());
+        assert_eq!(1392, tx.udp().stub_data_expected_len_ts);
+        assert_eq!(2, tx.frag_cnt_ts);
+    }
+
+    #[test]
+    fn test_broadcast_responses() {
+        let mut request = DCOM_REQUEST.to_vec();
+        request[2] |= PFCL1_LASTFRAG | PFCL1_BROADCAST | PFCL1_IDEMPOTENT | PFCL1_MAYBE;
+        let mut response = DCOM_REQUEST.to_vec();
+        response[1] = DCERPC_TYPE_RESPONSE;
+        response[2] = PFCL1_NOFACK;
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        // responses from two servers
+        for server_boot in [1u8, 2u8] {
+            response[56] = server_boot;
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&response));
+        }
+        // retransmission by the second server
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&response));
+
+        assert_eq!(2, dcerpcudp_state.transactions.len());
+        assert_eq!(0, dcerpcudp_state.events);
+        for (tx, server_boot) in dcerpcudp_state.transactions.iter().zip([1, 2]) {
+            assert!(tx.udp().broadcast);
+            assert!(tx.req_done && tx.resp_done);
+            assert_eq!(server_boot, tx.udp().server_boot);
+            assert_eq!(1392, tx.stub_data_buffer_tc.len());
+        }
+        assert_eq!(1392, dcerpcudp_state.transactions[0].stub_data_buffer_ts.len());
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        // two PDUs in one datagram
+        let mut request = DCOM_REQUEST.to_vec();
+        request.extend_from_slice(&dcom_fragment(1));
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        // bad version
+        let mut bad = DCOM_REQUEST.to_vec();
+        bad[0] = 5;
+        assert_eq!(AppLayerResult::err(), dcerpcudp_state.handle_input_data(&bad));
+        // unsupported packet type, its body raises an event
+        let mut ping = DCOM_REQUEST.to_vec();
+        ping[1] = DCERPC_TYPE_PING;
+        assert_eq!(AppLayerResult::err(), dcerpcudp_state.handle_input_data(&ping));
+        // response without request
+        let mut response = DCOM_REQUEST.to_vec();
+        response[1] = DCERPC_TYPE_RESPONSE;
+        response[64] = 0x01; // seqnum
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&response));
+        // a PDU split over two calls, its header is counted once
+        let mut request = DCOM_REQUEST.to_vec();
+        request[64] = 0x02; // seqnum
+        assert_eq!(
+            AppLayerResult::incomplete(1000, request.len() as u32 - 1000),
+            dcerpcudp_state.handle_input_data(&request[..1000])
+        );
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request[1000..]));
+
+        let state = &mut dcerpcudp_state as *mut DCERPCUDPState as *mut std::os::raw::c_void;
+        let mut stats = DCERPCUDPStats::default();
+        unsafe { rs_dcerpc_udp_get_stats(state, &mut stats) };
+        assert_eq!(
+            DCERPCUDPStats {
+                datagrams: 6,
+                headers_ok: 5,
+                headers_err: 1,
+                reassembly_failures: 1,
+                events_raised: 2,
+            },
+            stats
+        );
+    }
+
+    #[test]
+    fn test_allowed_byte_orders() {
+        let little = DCERPCUdpHeaderBuilder::new().drep_byte_order(true).build();
+        let big = DCERPCUdpHeaderBuilder::new().drep_byte_order(false).build();
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(DCERPC_UDP_BYTE_ORDER_ANY, dcerpcudp_state.allowed_byte_orders);
+        dcerpcudp_state.allowed_byte_orders = DCERPC_UDP_BYTE_ORDER_LITTLE;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&little));
+        assert_eq!(0, dcerpcudp_state.events);
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.allowed_byte_orders = DCERPC_UDP_BYTE_ORDER_LITTLE;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&big));
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        assert_eq!(1, dcerpcudp_state.events);
+    }
+
+    #[test]
+    fn test_get_tx_data_per_tx() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let first = DCERPCUdpHeaderBuilder::new().seqnum(1).build();
+        let second = DCERPCUdpHeaderBuilder::new().seqnum(2).build();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&first));
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&second));
+        assert_eq!(2, dcerpcudp_state.transactions.len());
+
+        let tx0 = &mut dcerpcudp_state.transactions[0] as *mut DCERPCTransaction;
+        let tx1 = &mut dcerpcudp_state.transactions[1] as *mut DCERPCTransaction;
+        let tx_data0 = unsafe { rs_dcerpc_udp_get_tx_data(tx0 as *mut std::os::raw::c_void) };
+        let tx_data1 = unsafe { rs_dcerpc_udp_get_tx_data(tx1 as *mut std::os::raw::c_void) };
+        assert_ne!(tx_data0, tx_data1);
+        assert_eq!(
+            &mut dcerpcudp_state.transactions[0].tx_data as *mut AppLayerTxData,
+            tx_data0
+        );
+    }
+
+    #[test]
+    fn test_handle_input_data_udp_short_hdr() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::incomplete(79, 1),
+            dcerpcudp_state.handle_input_data(&DCOM_REQUEST[..79])
+        );
+        assert_eq!(79, dcerpcudp_state.buffer.len());
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let mut request = DCOM_REQUEST[..40].to_vec();
+        request[0] = 5;
+        assert_eq!(AppLayerResult::err(), dcerpcudp_state.handle_input_data(&request));
+        assert!(dcerpcudp_state.buffer.is_empty());
+        assert_eq!(1, dcerpcudp_state.stats.headers_err);
+    }
+
+    #[test]
+    fn test_interface_uuids() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let mut iface = [0; 16];
+        for seqnum in 0..4 {
+            iface[0] = (seqnum % 2) as u8 + 1;
+            let request = DCERPCUdpHeaderBuilder::new()
+                .interface_uuid(iface)
+                .seqnum(seqnum)
+                .build();
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        }
+        assert_eq!(4, dcerpcudp_state.transactions.len());
+
+        let state = &mut dcerpcudp_state as *mut DCERPCUDPState as *mut std::os::raw::c_void;
+        let mut buf: *const u8 = std::ptr::null();
+        let mut len: u32 = 0;
+        let mut uuids = Vec::new();
+        let mut idx = 0;
+        while unsafe { rs_dcerpc_udp_get_interface_uuid(state, idx, &mut buf, &mut len) } == 1 {
+            uuids.push(unsafe { std::slice::from_raw_parts(buf, len as usize) }.to_vec());
+            idx += 1;
+        }
+        assert_eq!(2, uuids.len());
+        assert_ne!(uuids[0], uuids[1]);
+
+        // the list doesn't grow past its limit
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        for seqnum in 0..DCERPC_UDP_MAX_INTERFACES as u32 + 10 {
+            iface[..4].copy_from_slice(&seqnum.to_be_bytes());
+            let request = DCERPCUdpHeaderBuilder::new()
+                .interface_uuid(iface)
+                .seqnum(seqnum)
+                .build();
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        }
+        assert_eq!(DCERPC_UDP_MAX_INTERFACES, dcerpcudp_state.interface_uuids.len());
+    }
+
+    #[test]
+    fn test_iface_version() {
+        // if_vers 0x00020003 is version 3.2, in either byte order
+        for little_endian in [true, false] {
+            let mut request = DCERPCUdpHeaderBuilder::new()
+                .drep_byte_order(little_endian)
+                .build();
+            let if_vers: u32 = 0x0002_0003;
+            let bytes = if little_endian { if_vers.to_le_bytes() } else { if_vers.to_be_bytes() };
+            request[60..64].copy_from_slice(&bytes);
+            let mut dcerpcudp_state = DCERPCUDPState::new();
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+            let tx = &mut dcerpcudp_state.transactions[0];
+            let mut major: u16 = 0;
+            let mut minor: u16 = 0;
+            unsafe { rs_dcerpc_udp_get_tx_iface_version(tx, &mut major, &mut minor) };
+            assert_eq!((3, 2), (major, minor));
+        }
+    }
+
+    #[test]
+    fn test_strip_stub_padding() {
+        let stub = [0x01, 0x02, 0x03, 0x04, 0x05, 0x00, 0x00, 0x00];
+        let mut request = DCERPCUdpHeaderBuilder::new().fraglen(stub.len() as u16).build();
+        request.extend_from_slice(&stub);
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert!(!dcerpcudp_state.strip_stub_padding);
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        assert_eq!(&stub, dcerpcudp_state.transactions[0].stub_data_buffer_ts.as_slice());
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.strip_stub_padding = true;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        assert_eq!(&stub[..5], dcerpcudp_state.transactions[0].stub_data_buffer_ts.as_slice());
+        assert_eq!(0, dcerpcudp_state.events);
+
+        // unaligned bodies have no padding
+        assert_eq!(0, stub_padding_len(&stub[..7]));
+        assert_eq!(7, stub_padding_len(&[0; 16]));
+    }
+
+    #[test]
+    fn test_parse_datagram_outcome() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            UdpParseOutcome {
+                consumed: 40,
+                needed: 40,
+                ..Default::default()
+            },
+            dcerpcudp_state.parse_datagram(&DCOM_REQUEST[..40])
+        );
+        assert_eq!(
+            UdpParseOutcome {
+                consumed: DCOM_REQUEST.len() - 40,
+                complete: true,
+                ..Default::default()
+            },
+            dcerpcudp_state.parse_datagram(&DCOM_REQUEST[40..])
+        );
+
+        let mut response = DCOM_REQUEST.to_vec();
+        response[1] = DCERPC_TYPE_RESPONSE;
+        response[64] = 0x01; // seqnum
+        assert_eq!(
+            UdpParseOutcome {
+                consumed: response.len(),
+                events: vec![DCERPCUDPEvent::ResponseWithoutRequest],
+                complete: true,
+                ..Default::default()
+            },
+            dcerpcudp_state.parse_datagram(&response)
+        );
+
+        let mut bad = DCOM_REQUEST.to_vec();
+        bad[0] = 5;
+        assert_eq!(
+            UdpParseOutcome {
+                error: true,
+                ..Default::default()
+            },
+            dcerpcudp_state.parse_datagram(&bad)
+        );
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(DCOM_REQUEST));
+        assert!(!dcerpcudp_state.transactions[0].req_done);
+
+        let mut cancel = DCOM_REQUEST[..DCERPC_UDP_HDR_LEN as usize].to_vec();
+        cancel[1] = DCERPC_TYPE_CL_CANCEL;
+        cancel[74] = 0; // fraglen
+        cancel[75] = 0;
+        // a cancel for an unknown call is ignored
+        cancel[64] = 0x01; // seqnum
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&cancel));
+        assert!(!dcerpcudp_state.transactions[0].udp().cancelled);
+
+        cancel[64] = 0x00;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&cancel));
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        let tx = &dcerpcudp_state.transactions[0];
+        assert!(tx.udp().cancelled);
+        assert!(tx.req_done);
+        assert!(tx.resp_done);
+        assert!(tx.resp_lost);
+        assert_eq!(1, dcerpcudp_state.events);
+
+        // the cancel ack doesn't raise the event again
+        cancel[1] = DCERPC_TYPE_CANCEL_ACK;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&cancel));
+        assert_eq!(1, dcerpcudp_state.events);
+    }
+
+    #[test]
+    fn test_stub_preview() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(DCOM_REQUEST));
+        let tx = &mut dcerpcudp_state.transactions[0];
+        let mut buf: *const u8 = std::ptr::null();
+        let mut len: u32 = 0;
+        let dir = Direction::ToServer as u8;
+        assert_eq!(1, unsafe { rs_dcerpc_udp_get_tx_stub_preview(tx, dir, 16, &mut buf, &mut len) });
+        let preview = unsafe { std::slice::from_raw_parts(buf, len as usize) };
+        assert_eq!(&DCOM_REQUEST[80..96], preview);
+
+        // capped at the stub length, empty without a stub
+        assert_eq!(1392, tx.stub_preview(Direction::ToServer, 4096).len());
+        let dir = Direction::ToClient as u8;
+        assert_eq!(0, unsafe { rs_dcerpc_udp_get_tx_stub_preview(tx, dir, 16, &mut buf, &mut len) });
+        assert_eq!(0, len);
+    }
+
+    #[test]
+    fn test_parse_failure_leaves_no_state() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let mut ping = DCOM_REQUEST.to_vec();
+        ping[1] = DCERPC_TYPE_PING;
+        assert_eq!(
+            AppLayerResult::incomplete(40, 40),
+            dcerpcudp_state.handle_input_data(&ping[..40])
+        );
+        assert!(dcerpcudp_state.has_pending());
+        // the rest of the header completes a PDU that is rejected
+        assert_eq!(AppLayerResult::err(), dcerpcudp_state.handle_input_data(&ping[40..]));
+        assert!(!dcerpcudp_state.has_pending());
+        assert!(dcerpcudp_state.transactions.is_empty());
+        assert_eq!(0, dcerpcudp_state.events);
+
+        // the next datagram is parsed on its own
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(DCOM_REQUEST));
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        assert_eq!(1392, dcerpcudp_state.transactions[0].stub_data_buffer_ts.len());
+    }
+
+    #[test]
+    fn test_sensitive_interface() {
+        // 367abb81-9844-35f1-ad32-98f038001003
+        let svcctl = [
+            0x36, 0x7a, 0xbb, 0x81, 0x98, 0x44, 0x35, 0xf1, 0xad, 0x32, 0x98, 0xf0, 0x38, 0x00,
+            0x10, 0x03,
+        ];
+        let svcctl_wire = [
+            0x81, 0xbb, 0x7a, 0x36, 0x44, 0x98, 0xf1, 0x35, 0xad, 0x32, 0x98, 0xf0, 0x38, 0x00,
+            0x10, 0x03,
+        ];
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.sensitive_interfaces.push(DCERPCUuid(svcctl));
+
+        let request = DCERPCUdpHeaderBuilder::new().build();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(0, rs_dcerpc_udp_tx_is_sensitive(tx));
+        assert_eq!(0, dcerpcudp_state.events);
+
+        // a new call of the activity, with its own serial number
+        let request = DCERPCUdpHeaderBuilder::new()
+            .interface_uuid(svcctl_wire)
+            .seqnum(1)
+            .serial(1)
+            .build();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let tx = &mut dcerpcudp_state.transactions[1];
+        assert_eq!(DCERPCUuid(svcctl), tx.udp().interfaceuuid);
+        assert_eq!(1, rs_dcerpc_udp_tx_is_sensitive(tx));
+        assert_eq!(1, dcerpcudp_state.events);
+    }
+
+    #[test]
+    fn test_req_done_on_last_frag() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&dcom_fragment(0)));
+        assert!(!dcerpcudp_state.transactions[0].req_done);
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&dcom_fragment(1)));
+        assert!(!dcerpcudp_state.transactions[0].req_done);
+        let mut last = dcom_fragment(2);
+        last[2] |= PFCL1_LASTFRAG;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&last));
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        let tx = &dcerpcudp_state.transactions[0];
+        assert!(tx.req_done);
+        assert_eq!(3, tx.frag_cnt_ts);
+        assert_eq!(3 * 1392, tx.stub_data_buffer_ts.len());
+    }
+
+    #[test]
+    fn test_fraglen_too_small() {
+        let mut request = DCERPCUdpHeaderBuilder::new().flags1(PFCL1_FRAG).fraglen(4).build();
+        request.extend_from_slice(&[1, 2, 3, 4]);
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        assert_eq!(1, dcerpcudp_state.events);
+
+        // last fragments and header only PDUs may be short
+        let mut request = DCERPCUdpHeaderBuilder::new()
+            .flags1(PFCL1_FRAG | PFCL1_LASTFRAG)
+            .fragnum(1)
+            .fraglen(4)
+            .build();
+        request.extend_from_slice(&[5, 6, 7, 8]);
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let request = DCERPCUdpHeaderBuilder::new().seqnum(1).build();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        assert_eq!(1, dcerpcudp_state.events);
+    }
+
+    #[test]
+    fn test_tx_timestamps() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let mut request = DCOM_REQUEST.to_vec();
+        request[2] |= PFCL1_LASTFRAG;
+        dcerpcudp_state.ts = 1_000_000;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let mut response = request.clone();
+        response[1] = DCERPC_TYPE_RESPONSE;
+        response[2] &= !PFCL1_LASTFRAG;
+        dcerpcudp_state.ts = 1_002_500;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&response));
+        // later fragments don't move the timestamps
+        response[2] |= PFCL1_LASTFRAG;
+        response[76] = 1; // fragnum
+        dcerpcudp_state.ts = 1_003_000;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&response));
+
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        let tx = &mut dcerpcudp_state.transactions[0];
+        let mut req_ts: u64 = 0;
+        let mut resp_ts: u64 = 0;
+        unsafe { rs_dcerpc_udp_get_tx_timestamps(tx, &mut req_ts, &mut resp_ts) };
+        assert_eq!(1_000_000, req_ts);
+        assert_eq!(1_002_500, resp_ts);
+        assert!(req_ts < resp_ts);
+    }
+
+    #[test]
+    fn test_response_joins_request_tx() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let mut request = DCOM_REQUEST.to_vec();
+        request[2] |= PFCL1_LASTFRAG;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let mut response = request.clone();
+        response[1] = DCERPC_TYPE_RESPONSE;
+        response[80] ^= 0xff;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&response));
+
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        assert_eq!(0, dcerpcudp_state.events);
+        let tx = &dcerpcudp_state.transactions[0];
+        assert!(tx.req_done && tx.resp_done);
+        assert_eq!(&request[80..], tx.stub_data_buffer_ts.as_slice());
+        assert_eq!(&response[80..], tx.stub_data_buffer_tc.as_slice());
+    }
+
+    #[test]
+    fn test_ndr_max_count() {
+        for little_endian in [true, false] {
+            let max_count: u32 = 0x0001_0000;
+            let mut request = DCERPCUdpHeaderBuilder::new()
+                .drep_byte_order(little_endian)
+                .fraglen(8)
+                .build();
+            if little_endian {
+                request.extend_from_slice(&max_count.to_le_bytes());
+            } else {
+                request.extend_from_slice(&max_count.to_be_bytes());
+            }
+            request.extend_from_slice(&[0x41; 4]);
+            let mut dcerpcudp_state = DCERPCUDPState::new();
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+            let tx = &mut dcerpcudp_state.transactions[0];
+            let mut count: u32 = 0;
+            let dir = Direction::ToServer as u8;
+            assert_eq!(1, unsafe { rs_dcerpc_udp_get_tx_ndr_max_count(tx, dir, &mut count) });
+            assert_eq!(max_count, count);
+            let dir = Direction::ToClient as u8;
+            assert_eq!(0, unsafe { rs_dcerpc_udp_get_tx_ndr_max_count(tx, dir, &mut count) });
+        }
+    }
+
+    #[test]
+    fn test_handle_input_data_udp_short_continuation() {
+        let mut request = DCERPCUdpHeaderBuilder::new().fraglen(64).build();
+        request.extend((0..64).map(|b| b as u8 + 0x20));
+        let (first, continuation) = request.split_at(DCERPC_UDP_HDR_LEN as usize + 24);
+        assert_ne!(4, continuation[0]);
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::incomplete(first.len() as u32, 40),
+            dcerpcudp_state.handle_input_data(first)
+        );
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(continuation));
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        let tx = &dcerpcudp_state.transactions[0];
+        assert!(tx.req_done);
+        assert_eq!(&request[80..], tx.stub_data_buffer_ts.as_slice());
+    }
+
+    #[test]
+    fn test_opnum_mismatch() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let request = DCERPCUdpHeaderBuilder::new().opnum(3).build();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let response = DCERPCUdpHeaderBuilder::new()
+            .pkt_type(DCERPCPktType::Response)
+            .opnum(7)
+            .build();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&response));
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        assert_eq!(1, dcerpcudp_state.events);
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(3, tx.opnum);
+        assert_eq!(7, rs_dcerpc_udp_get_tx_response_opnum(tx));
+
+        // matching opnums
+        let request = DCERPCUdpHeaderBuilder::new().opnum(3).seqnum(1).build();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let response = DCERPCUdpHeaderBuilder::new()
+            .pkt_type(DCERPCPktType::Response)
+            .opnum(3)
+            .seqnum(1)
+            .build();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&response));
+        assert_eq!(2, dcerpcudp_state.transactions.len());
+        assert_eq!(1, dcerpcudp_state.events);
+    }
+
+    #[test]
+    fn test_stub_slice() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(DCOM_REQUEST));
+        let stub = dcerpcudp_state.stub_slice(0, Direction::ToServer).unwrap();
+        assert_eq!(dcerpcudp_state.transactions[0].stub_data_buffer_ts.len(), stub.len());
+        assert_eq!(
+            dcerpcudp_state.transactions[0].stub_data_buffer_ts.as_ptr(),
+            stub.as_ptr()
+        );
+        assert_eq!(Some(&[][..]), dcerpcudp_state.stub_slice(0, Direction::ToClient));
+        assert_eq!(None, dcerpcudp_state.stub_slice(1, Direction::ToServer));
+    }
+
+    #[test]
+    fn test_fault() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let request = DCERPCUdpHeaderBuilder::new().build();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        // nca_s_fault_access_denied
+        let mut fault = DCERPCUdpHeaderBuilder::new()
+            .pkt_type(DCERPCPktType::Fault)
+            .fraglen(4)
+            .build();
+        fault.extend_from_slice(&0x1c000005_u32.to_le_bytes());
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&fault));
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        assert_eq!(1, dcerpcudp_state.events);
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert!(tx.req_done && tx.resp_done);
+        assert_eq!(DCERPC_TYPE_FAULT, tx.resp_cmd);
+        let mut status: u32 = 0;
+        assert_eq!(1, unsafe { rs_dcerpc_udp_get_tx_fault_status(tx, &mut status) });
+        assert_eq!(0x1c000005, status);
+        assert_eq!(0, unsafe { rs_dcerpc_udp_get_tx_fault_status(tx, std::ptr::null_mut()) });
+
+        // a retransmitted fault is not counted again
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&fault));
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        assert_eq!(1, dcerpcudp_state.events);
+
+        // faults for unknown calls respect the transaction limit
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.max_transactions = 2;
+        for seqnum in 0..5 {
+            let mut fault = DCERPCUdpHeaderBuilder::new()
+                .pkt_type(DCERPCPktType::Fault)
+                .seqnum(seqnum)
+                .fraglen(4)
+                .build();
+            fault.extend_from_slice(&0x1c000005_u32.to_le_bytes());
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&fault));
+            assert!(dcerpcudp_state.transactions.len() <= 2);
+        }
+        let seqnums: Vec<u32> = dcerpcudp_state.transactions.iter().map(|tx| tx.seqnum).collect();
+        assert_eq!(vec![3, 4], seqnums);
+
+        // the flow event count saturates
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.events = u16::MAX;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&fault));
+        assert_eq!(u16::MAX, dcerpcudp_state.events);
+    }
+
+    #[test]
+    fn test_fragment_log() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&dcom_fragment(0)));
+        assert!(dcerpcudp_state.transactions[0].udp().fragment_log.is_empty());
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.fragment_log = true;
+        let mut last = dcom_fragment(1);
+        last[2] |= PFCL1_LASTFRAG;
+        for request in [dcom_fragment(0), dcom_fragment(2), last] {
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        }
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(3, rs_dcerpc_udp_get_tx_fragment_log_cnt(tx));
+        let mut frags = Vec::new();
+        let mut frag = DCERPCFragMeta::default();
+        while unsafe { rs_dcerpc_udp_get_tx_fragment_log(tx, frags.len() as u32, &mut frag) } == 1 {
+            frags.push(frag);
+        }
+        assert_eq!(
+            vec![(0, 1), (2, 2), (1, 3)],
+            frags.iter().map(|f| (f.fragnum, f.arrival_order)).collect::<Vec<_>>()
+        );
+        assert!(frags.iter().all(|f| f.len == 1392));
+        assert_eq!(0x2e, frags[2].flags1);
+    }
+
+    #[test]
+    fn test_parse_udp_empty_input() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let state = &mut dcerpcudp_state as *mut DCERPCUDPState as *mut std::os::raw::c_void;
+        let parse = |input: &[u8]| unsafe {
+            rs_dcerpc_udp_parse(
+                std::ptr::null(), state, std::ptr::null_mut(),
+                StreamSlice::from_slice(input, core::STREAM_TOSERVER, 0), std::ptr::null(),
+            )
+        };
+
+        assert_eq!(AppLayerResult::ok(), parse(&[]));
+        assert!(dcerpcudp_state.buffer.is_empty());
+        let mut bad = DCOM_REQUEST.to_vec();
+        bad[0] = 5;
+        assert_eq!(AppLayerResult::err(), parse(&bad));
+        // only the malformed datagram was parsed
+        assert_eq!(1, dcerpcudp_state.stats.datagrams);
+    }
+
+    #[test]
+    fn test_tx_byte_counts() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        // a fragment restarting the PDU resets the stub, not the count
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&dcom_fragment(0)));
+        let mut request = dcom_fragment(0);
+        request[2] |= PFCL1_LASTFRAG;
+        request.truncate(DCERPC_UDP_HDR_LEN as usize + 100);
+        request[74..76].copy_from_slice(&100u16.to_le_bytes());
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(100, tx.stub_data_buffer_ts.len());
+        let mut bytes_ts: u64 = 0;
+        let mut bytes_tc: u64 = 0;
+        unsafe { rs_dcerpc_udp_get_tx_byte_counts(tx, &mut bytes_ts, &mut bytes_tc) };
+        assert_eq!(1392 + 100, bytes_ts);
+        assert_eq!(0, bytes_tc);
+    }
+
+    #[test]
+    fn test_unknown_pkt_type_strict() {
+        let mut request = DCERPCUdpHeaderBuilder::new().flags1(PFCL1_LASTFRAG).build();
+        request[1] = 0x20;
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let outcome = dcerpcudp_state.parse_datagram(&request);
+        assert!(outcome.error);
+        assert!(outcome.events.is_empty());
+        assert_eq!(0, dcerpcudp_state.events);
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.strict = true;
+        let outcome = dcerpcudp_state.parse_datagram(&request);
+        assert!(outcome.complete && !outcome.error);
+        assert_eq!(vec![DCERPCUDPEvent::UnknownPktType], outcome.events);
+        assert_eq!(1, dcerpcudp_state.events);
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        let tx = &dcerpcudp_state.transactions[0];
+        assert!(tx.req_done && tx.resp_done);
+
+        // packet types the protocol defines are not anomalous
+        request[1] = DCERPC_TYPE_PING;
+        let outcome = dcerpcudp_state.parse_datagram(&request);
+        assert!(outcome.events.is_empty());
+    }
+
+    #[test]
+    fn test_transactions_by_interface() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        for (seqnum, iface) in [(1, 0x11), (2, 0x22), (3, 0x11)] {
+            let request = DCERPCUdpHeaderBuilder::new()
+                .flags1(PFCL1_LASTFRAG)
+                .interface_uuid([iface; 16])
+                .seqnum(seqnum)
+                .build();
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        }
+        let groups = dcerpcudp_state.transactions_by_interface();
+        assert_eq!(2, groups.len());
+        let seqnums = |iface: u8| groups[&[iface; 16]].iter().map(|tx| tx.seqnum).collect::<Vec<_>>();
+        assert_eq!(vec![1, 3], seqnums(0x11));
+        assert_eq!(vec![2], seqnums(0x22));
+    }
+
+    #[test]
+    fn test_non_monotonic_fragnum() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let mut last = dcom_fragment(3);
+        last[2] |= PFCL1_LASTFRAG;
+        let mut events = Vec::new();
+        for request in [dcom_fragment(0), dcom_fragment(1), dcom_fragment(1), last] {
+            let outcome = dcerpcudp_state.parse_datagram(&request);
+            assert!(outcome.complete);
+            events.push(outcome.events);
+        }
+        assert_eq!(
+            vec![vec![], vec![], vec![DCERPCUDPEvent::NonMonotonicFragnum], vec![]],
+            events
+        );
+    }
+
+    #[test]
+    fn test_conversation_state() {
+        let request = DCERPCUdpHeaderBuilder::new().flags1(PFCL1_LASTFRAG).build();
+        let response = DCERPCUdpHeaderBuilder::new()
+            .pkt_type(DCERPCPktType::Response)
+            .flags1(PFCL1_LASTFRAG)
+            .build();
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(ConversationState::RequestOnly, tx.conversation_state());
+        assert_eq!(0, rs_dcerpc_udp_get_tx_conversation_state(tx));
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&response));
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(ConversationState::Complete, tx.conversation_state());
+        assert_eq!(2, rs_dcerpc_udp_get_tx_conversation_state(tx));
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&response));
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(ConversationState::ResponseOnly, tx.conversation_state());
+        assert_eq!(1, rs_dcerpc_udp_get_tx_conversation_state(tx));
+
+        // a partial response doesn't complete the call
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let mut partial = DCERPCUdpHeaderBuilder::new()
+            .pkt_type(DCERPCPktType::Response)
+            .flags1(PFCL1_FRAG)
+            .fraglen(8)
+            .build();
+        partial.extend_from_slice(&[0; 8]);
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&partial));
+        let tx = &dcerpcudp_state.transactions[0];
+        assert_eq!(ConversationState::RequestOnly, tx.conversation_state());
+    }
+
+    #[test]
+    fn test_epm_endpoints() {
+        // e1af8308-5d1f-11c9-91a4-08002b14a0fa
+        let epm_wire = [
+            0x08, 0x83, 0xaf, 0xe1, 0x1f, 0x5d, 0xc9, 0x11, 0x91, 0xa4, 0x08, 0x00, 0x2b, 0x14,
+            0xa0, 0xfa,
+        ];
+        let mut tower = vec![5, 0];
+        for (lhs, rhs) in [
+            (&[0x0d; 19][..], &[0, 0][..]),
+            (&[0x0d; 19][..], &[0, 0][..]),
+            (&[0x0b][..], &[0, 0][..]),
+            (&[0x07][..], &[0xc0, 0x02][..]),
+            (&[0x09][..], &[10, 0, 0, 1][..]),
+        ] {
+            tower.extend_from_slice(&(lhs.len() as u16).to_le_bytes());
+            tower.extend_from_slice(lhs);
+            tower.extend_from_slice(&(rhs.len() as u16).to_le_bytes());
+            tower.extend_from_slice(rhs);
+        }
+        let mut stub = vec![0; 20];
+        for v in [1u32, 1, 0, 1, 0x03, tower.len() as u32, tower.len() as u32] {
+            stub.extend_from_slice(&v.to_le_bytes());
+        }
+        stub.extend_from_slice(&tower);
+        while stub.len() & 3 != 0 {
+            stub.push(0);
+        }
+        stub.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let request = DCERPCUdpHeaderBuilder::new()
+            .flags1(PFCL1_LASTFRAG)
+            .interface_uuid(epm_wire)
+            .opnum(3)
+            .build();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let mut response = DCERPCUdpHeaderBuilder::new()
+            .pkt_type(DCERPCPktType::Response)
+            .flags1(PFCL1_LASTFRAG)
+            .interface_uuid(epm_wire)
+            .opnum(3)
+            .fraglen(stub.len() as u16)
+            .build();
+        response.extend_from_slice(&stub);
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&response));
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(vec!["ncacn_ip_tcp:10.0.0.1[49154]".to_string()], tx.udp().epm_endpoints);
+        let mut buf: *const u8 = std::ptr::null();
+        let mut len: u32 = 0;
+        assert_eq!(1, unsafe { rs_dcerpc_udp_get_tx_epm_endpoints(tx, 0, &mut buf, &mut len) });
+        assert_eq!(b"ncacn_ip_tcp:10.0.0.1[49154]", unsafe { std::slice::from_raw_parts(buf, len as usize) });
+        assert_eq!(0, unsafe { rs_dcerpc_udp_get_tx_epm_endpoints(tx, 1, &mut buf, &mut len) });
+    }
+
+    #[test]
+    fn test_parse_adversarial_fraglen() {
+        let fraglens: [u16; 13] = [0, 1, 7, 8, 79, 80, 1391, 1392, 1393, 0x7fff, 0x8000, 0xfffe, 0xffff];
+        for &fraglen in &fraglens {
+            for little_endian in [true, false] {
+                for cut in [DCERPC_UDP_HDR_LEN as usize, 100, DCOM_REQUEST.len()] {
+                    let mut request = DCOM_REQUEST[..cut].to_vec();
+                    if !little_endian {
+                        request[4] = 0x00;
+                    }
+                    let bytes = if little_endian { fraglen.to_le_bytes() } else { fraglen.to_be_bytes() };
+                    request[74..76].copy_from_slice(&bytes);
+                    request[2] |= PFCL1_LASTFRAG;
+                    // the same PDU twice in one datagram, then on its own
+                    let mut datagram = request.clone();
+                    datagram.extend_from_slice(&request);
+                    let mut dcerpcudp_state = DCERPCUDPState::new();
+                    for input in [&datagram, &request] {
+                        let outcome = dcerpcudp_state.parse_datagram(input);
+                        assert!(outcome.consumed <= input.len());
+                        assert!(outcome.error || outcome.complete || outcome.needed > 0);
+                    }
+                    for tx in &dcerpcudp_state.transactions {
+                        assert!(tx.stub_data_buffer_ts.len() <= DCOM_REQUEST.len());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_foreach_tx() {
+        extern "C" fn collect(tx: *mut DCERPCTransaction, user_data: *mut std::os::raw::c_void) {
+            let call_ids = unsafe { &mut *(user_data as *mut Vec<(u64, u32)>) };
+            let tx = unsafe { &*tx };
+            call_ids.push((tx.id, tx.call_id));
+        }
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        for (seqnum, serial) in [(1, 3), (2, 1), (3, 2)] {
+            let request = DCERPCUdpHeaderBuilder::new()
+                .flags1(PFCL1_LASTFRAG)
+                .seqnum(seqnum)
+                .serial(serial)
+                .build();
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        }
+        let mut call_ids: Vec<(u64, u32)> = Vec::new();
+        unsafe {
+            rs_dcerpc_udp_foreach_tx(
+                &mut dcerpcudp_state as *mut _ as *mut std::os::raw::c_void,
+                &mut call_ids as *mut _ as *mut std::os::raw::c_void,
+                collect,
+            );
+        }
+        assert_eq!(vec![(0, 3), (1, 1), (2, 2)], call_ids);
+    }
+
+    #[test]
+    fn test_possible_rpc_scan() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.scan_threshold = 4;
+        let mut scan_events = Vec::new();
+        for seqnum in 0..6 {
+            let request = DCERPCUdpHeaderBuilder::new()
+                .flags1(PFCL1_LASTFRAG)
+                .activity_uuid([0x33; 16])
+                .seqnum(seqnum)
+                .serial(seqnum as u16)
+                .build();
+            let outcome = dcerpcudp_state.parse_datagram(&request);
+            scan_events.push(outcome.events.contains(&DCERPCUDPEvent::PossibleRpcScan));
+        }
+        assert_eq!(vec![false, false, false, true, false, false], scan_events);
+
+        // requests that get a response are not counted
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.scan_threshold = 2;
+        for seqnum in 0..4 {
+            for pkt_type in [DCERPCPktType::Request, DCERPCPktType::Response] {
+                let pdu = DCERPCUdpHeaderBuilder::new()
+                    .pkt_type(pkt_type)
+                    .flags1(PFCL1_LASTFRAG)
+                    .seqnum(seqnum)
+                    .build();
+                let outcome = dcerpcudp_state.parse_datagram(&pdu);
+                assert!(!outcome.events.contains(&DCERPCUDPEvent::PossibleRpcScan));
+            }
+        }
+    }
+
+    #[test]
+    fn test_working_and_nocall() {
+        let request = DCERPCUdpHeaderBuilder::new().flags1(PFCL1_LASTFRAG).seqnum(1).build();
+        let working = DCERPCUdpHeaderBuilder::new().pkt_type(DCERPCPktType::Working).seqnum(1).build();
+        let nocall = DCERPCUdpHeaderBuilder::new().pkt_type(DCERPCPktType::Nocall).seqnum(1).build();
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let outcome = dcerpcudp_state.parse_datagram(&working);
+        assert!(outcome.complete);
+        assert!(outcome.events.is_empty());
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        let tx = &dcerpcudp_state.transactions[0];
+        assert!(tx.udp().server_working);
+        assert_eq!(0, tx.frag_cnt_tc);
+        assert!(!tx.resp_done);
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let outcome = dcerpcudp_state.parse_datagram(&nocall);
+        assert!(outcome.complete);
+        assert_eq!(vec![DCERPCUDPEvent::NoCall], outcome.events);
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        let tx = &dcerpcudp_state.transactions[0];
+        assert!(!tx.udp().server_working);
+        assert_eq!(0, tx.frag_cnt_tc);
+
+        // without a matching call there is nothing to update
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&nocall));
+        assert!(dcerpcudp_state.transactions.is_empty());
+    }
+
+    #[test]
+    fn test_reconstruct_pdu() {
+        let mut request = DCOM_REQUEST.to_vec();
+        request[2] = PFCL1_IDEMPOTENT;
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let pdu = dcerpcudp_state.reconstruct_pdu(0, Direction::ToServer).unwrap();
+        assert_eq!(&request[80..], &pdu[80..]);
+        assert_eq!(request, pdu);
+
+        let (rem, header) = parser::parse_dcerpc_udp_header(&pdu).unwrap();
+        assert_eq!(request.len() - 80, rem.len());
+        let tx = &dcerpcudp_state.transactions[0];
+        assert_eq!(tx.activityuuid, header.activityuuid);
+        assert_eq!(tx.udp().interfaceuuid, header.interfaceuuid);
+        assert_eq!(tx.opnum, header.opnum);
+
+        assert_eq!(None, dcerpcudp_state.reconstruct_pdu(0, Direction::ToClient));
+        assert_eq!(None, dcerpcudp_state.reconstruct_pdu(1, Direction::ToServer));
+    }
+
+    #[test]
+    fn test_tx_byte_order() {
+        let big = DCERPCUdpHeaderBuilder::new()
+            .flags1(PFCL1_LASTFRAG)
+            .drep_byte_order(false)
+            .seqnum(1)
+            .build();
+        let little = DCERPCUdpHeaderBuilder::new().flags1(PFCL1_LASTFRAG).seqnum(2).build();
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&big));
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&little));
+        assert_eq!(
+            DCERPC_UDP_BYTE_ORDER_BIG,
+            rs_dcerpc_udp_get_tx_byte_order(&mut dcerpcudp_state.transactions[0])
+        );
+        assert_eq!(
+            DCERPC_UDP_BYTE_ORDER_LITTLE,
+            rs_dcerpc_udp_get_tx_byte_order(&mut dcerpcudp_state.transactions[1])
+        );
+    }
+
+    #[test]
+    fn test_header_only_request_done() {
+        // fraglen doesn't include the header, a bodyless PDU has a fraglen
+        // of 0 and completes on its last fragment like any other
+        let request = DCERPCUdpHeaderBuilder::new().flags1(PFCL1_IDEMPOTENT).build();
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let tx = &dcerpcudp_state.transactions[0];
+        assert!(tx.req_done);
+        assert!(!tx.resp_done);
+        assert_eq!(1, tx.frag_cnt_ts);
+        assert!(tx.stub_data_buffer_ts.is_empty());
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let first = DCERPCUdpHeaderBuilder::new().flags1(PFCL1_FRAG).build();
+        let last = DCERPCUdpHeaderBuilder::new()
+            .flags1(PFCL1_FRAG | PFCL1_LASTFRAG)
+            .fragnum(1)
+            .build();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&first));
+        assert!(!dcerpcudp_state.transactions[0].req_done);
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&last));
+        assert!(dcerpcudp_state.transactions[0].req_done);
+    }
+
+    #[test]
+    fn test_header_bytes() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&dcom_fragment(0)));
+        let mut last = dcom_fragment(1);
+        last[2] |= PFCL1_LASTFRAG;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&last));
+        let tx = &mut dcerpcudp_state.transactions[0];
+        let mut buf: *const u8 = std::ptr::null();
+        let mut len: u32 = 0;
+        unsafe { rs_dcerpc_udp_get_header_bytes(tx, &mut buf, &mut len) };
+        assert_eq!(DCERPC_UDP_HDR_LEN as u32, len);
+        let header = unsafe { std::slice::from_raw_parts(buf, len as usize) };
+        assert_eq!(&DCOM_REQUEST[..DCERPC_UDP_HDR_LEN as usize], header);
+
+        let mut tx = DCERPCTransaction::default();
+        unsafe { rs_dcerpc_udp_get_header_bytes(&mut tx, &mut buf, &mut len) };
+        assert!(buf.is_null());
+        assert_eq!(0, len);
+    }
+
+    #[test]
+    fn test_max_transactions() {
+        let request = |seqnum: u32| {
+            DCERPCUdpHeaderBuilder::new().flags1(PFCL1_LASTFRAG).seqnum(seqnum).build()
+        };
+        let response = |seqnum: u32| {
+            DCERPCUdpHeaderBuilder::new()
+                .pkt_type(DCERPCPktType::Response)
+                .flags1(PFCL1_LASTFRAG)
+                .seqnum(seqnum)
+                .build()
+        };
+
+        // completed transactions are evicted, oldest first
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.max_transactions = 4;
+        for seqnum in 0..10 {
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request(seqnum)));
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&response(seqnum)));
+            assert!(dcerpcudp_state.transactions.len() <= 4);
+        }
+        let seqnums: Vec<u32> = dcerpcudp_state.transactions.iter().map(|tx| tx.seqnum).collect();
+        assert_eq!(vec![6, 7, 8, 9], seqnums);
+        assert_eq!(0, dcerpcudp_state.events);
+
+        // without completed transactions new calls are dropped
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.max_transactions = 4;
+        let mut events = Vec::new();
+        for seqnum in 0..10 {
+            let outcome = dcerpcudp_state.parse_datagram(&request(seqnum));
+            assert!(outcome.complete);
+            events.extend(outcome.events);
+        }
+        assert_eq!(4, dcerpcudp_state.transactions.len());
+        assert_eq!(vec![DCERPCUDPEvent::TooManyTransactions], events);
+
+        // a call completing makes room again
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&response(0)));
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request(10)));
+        assert_eq!(4, dcerpcudp_state.transactions.len());
+        assert_eq!(10, dcerpcudp_state.transactions[3].seqnum);
+    }
+
+    #[test]
+    fn test_server_boot_changed() {
+        let pdu = |pkt_type: DCERPCPktType, seqnum: u32, server_boot: u32| {
+            DCERPCUdpHeaderBuilder::new()
+                .pkt_type(pkt_type)
+                .flags1(PFCL1_LASTFRAG)
+                .seqnum(seqnum)
+                .server_boot(server_boot)
+                .build()
+        };
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let mut events = Vec::new();
+        for (pkt_type, seqnum, server_boot) in [
+            (DCERPCPktType::Request, 1, 0),
+            (DCERPCPktType::Response, 1, 1000),
+            (DCERPCPktType::Request, 2, 1000),
+            (DCERPCPktType::Response, 2, 2000),
+        ] {
+            let outcome = dcerpcudp_state.parse_datagram(&pdu(pkt_type, seqnum, server_boot));
+            events.push(outcome.events);
+        }
+        assert_eq!(vec![vec![], vec![], vec![], vec![DCERPCUDPEvent::ServerBootChanged]], events);
+        assert_eq!(1, dcerpcudp_state.events);
+
+        // once the table is full new activities are not tracked
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        for n in 0..DCERPC_UDP_MAX_ACTIVITIES as u32 {
+            let mut uuid = [0xff; 16];
+            uuid[..4].copy_from_slice(&n.to_be_bytes());
+            dcerpcudp_state.server_boots.insert(DCERPCUuid(uuid), 1);
+        }
+        for (pkt_type, seqnum, server_boot) in [
+            (DCERPCPktType::Response, 1, 1000),
+            (DCERPCPktType::Response, 2, 2000),
+        ] {
+            let outcome = dcerpcudp_state.parse_datagram(&pdu(pkt_type, seqnum, server_boot));
+            assert!(!outcome.events.contains(&DCERPCUDPEvent::ServerBootChanged));
+        }
+        assert_eq!(DCERPC_UDP_MAX_ACTIVITIES, dcerpcudp_state.server_boots.len());
+    }
+
+    #[test]
+    fn test_process_header_errors() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let (consumed, header) = dcerpcudp_state.process_header(DCOM_REQUEST).unwrap();
+        assert_eq!(DCERPC_UDP_HDR_LEN as usize, consumed);
+        assert_eq!(0x2c, header.flags1);
 
-/// Probe input to see if it looks like DCERPC.
-fn probe(input: &[u8]) -> (bool, bool) {
-    match parser::parse_dcerpc_udp_header(input) {
-        Ok((_, hdr)) => {
-            let is_request = hdr.pkt_type == 0x00;
-            let is_dcerpc = hdr.rpc_vers == 0x04 &&
-                (hdr.flags2 & 0xfc == 0) &&
-                (hdr.drep[0] & 0xee == 0) &&
-                (hdr.drep[1] <= 3);
-            return (is_dcerpc, is_request);
-        },
-        Err(_) => (false, false),
+        assert_eq!(
+            Err(HeaderError::Incomplete(40)),
+            dcerpcudp_state.process_header(&DCOM_REQUEST[..40]).map(|(consumed, _)| consumed)
+        );
+        let mut request = DCOM_REQUEST.to_vec();
+        request[0] = 5;
+        for input in [&request[..], &request[..40]] {
+            assert_eq!(
+                Err(HeaderError::BadVersion),
+                dcerpcudp_state.process_header(input).map(|(consumed, _)| consumed)
+            );
+        }
+        let mut request = DCOM_REQUEST.to_vec();
+        request[4] = 0x20;
+        assert_eq!(
+            Err(HeaderError::Malformed),
+            dcerpcudp_state.process_header(&request).map(|(consumed, _)| consumed)
+        );
+        assert!(dcerpcudp_state.transactions.is_empty());
+
+        // and what the parser makes of them
+        assert_eq!(
+            AppLayerResult::incomplete(40, 40),
+            dcerpcudp_state.handle_input_data(&DCOM_REQUEST[..40])
+        );
+        dcerpcudp_state.buffer.clear();
+        assert_eq!(AppLayerResult::err(), dcerpcudp_state.handle_input_data(&request));
+        assert_eq!(1, dcerpcudp_state.stats.headers_err);
     }
-}
 
-pub unsafe extern "C" fn rs_dcerpc_probe_udp(_f: *const core::Flow, direction: u8, input: *const u8,
-                                      len: u32, rdir: *mut u8) -> core::AppProto
-{
-    SCLogDebug!("Probing the packet for DCERPC/UDP");
-    if len == 0 {
-        return core::ALPROTO_UNKNOWN;
+    #[test]
+    fn test_tx_complete_callback() {
+        extern "C" fn count(tx: *mut DCERPCTransaction, user_data: *mut std::os::raw::c_void) {
+            let completed = unsafe { &mut *(user_data as *mut Vec<u32>) };
+            completed.push(unsafe { &*tx }.seqnum);
+        }
+        let pdu = |pkt_type: DCERPCPktType, seqnum: u32| {
+            DCERPCUdpHeaderBuilder::new()
+                .pkt_type(pkt_type)
+                .flags1(PFCL1_LASTFRAG)
+                .seqnum(seqnum)
+                .build()
+        };
+        let mut completed: Vec<u32> = Vec::new();
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        unsafe {
+            rs_dcerpc_udp_set_tx_complete_callback(
+                &mut dcerpcudp_state as *mut _ as *mut std::os::raw::c_void,
+                Some(count),
+                &mut completed as *mut _ as *mut std::os::raw::c_void,
+            );
+        }
+        for (pkt_type, seqnum) in [
+            (DCERPCPktType::Request, 1),
+            (DCERPCPktType::Request, 2),
+            (DCERPCPktType::Response, 2),
+            (DCERPCPktType::Response, 2),
+            (DCERPCPktType::Response, 1),
+            (DCERPCPktType::Request, 3),
+        ] {
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&pdu(pkt_type, seqnum)));
+        }
+        assert_eq!(vec![2, 1], completed);
+        // calls still open at the end of the flow complete as well
+        dcerpcudp_state.handle_eof();
+        assert_eq!(vec![2, 1, 3], completed);
     }
-    let slice: &[u8] = std::slice::from_raw_parts(input as *mut u8, len as usize);
-    //is_incomplete is checked by caller
-    let (is_dcerpc, is_request) = probe(slice);
-    if is_dcerpc {
-        let dir: Direction = (direction & DIR_BOTH).into();
-        if is_request {
-            if dir != Direction::ToServer {
-                *rdir = Direction::ToServer.into();
-            }
-        } else if dir != Direction::ToClient {
-            *rdir = Direction::ToClient.into();
+
+    #[test]
+    fn test_stub_strings() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let mut request = DCOM_REQUEST.to_vec();
+        request[2] |= PFCL1_LASTFRAG;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(&["\\\\111111111111111111".to_string()], tx.stub_strings(Direction::ToServer));
+        assert!(tx.stub_strings(Direction::ToClient).is_empty());
+        let mut buf: *const u8 = std::ptr::null();
+        let mut len: u32 = 0;
+        let dir = Direction::ToServer as u8;
+        assert_eq!(1, unsafe { rs_dcerpc_udp_get_tx_stub_string(tx, dir, 0, &mut buf, &mut len) });
+        let string = unsafe { std::slice::from_raw_parts(buf, len as usize) };
+        assert_eq!(b"\\\\111111111111111111", string);
+        assert_eq!(0, unsafe { rs_dcerpc_udp_get_tx_stub_string(tx, dir, 1, &mut buf, &mut len) });
+
+        // bounded in count and length
+        let mut stub = Vec::new();
+        for _ in 0..DCERPC_UDP_STUB_STRINGS_MAX + 1 {
+            stub.extend_from_slice(&[b'a', 0].repeat(300));
+            stub.extend_from_slice(&[0, 0]);
+        }
+        let strings = find_utf16_strings(&stub);
+        assert_eq!(DCERPC_UDP_STUB_STRINGS_MAX, strings.len());
+        assert!(strings.iter().all(|s| s.len() == DCERPC_UDP_STUB_STRING_MAX_LEN));
+        assert!(find_utf16_strings(&[b'a', 0, b'b', 0, b'c', 0]).is_empty());
+    }
+
+    #[test]
+    fn test_current_tx_id() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let state = &mut dcerpcudp_state as *mut _ as *mut std::os::raw::c_void;
+        assert_eq!(DCERPC_UDP_NO_TX, unsafe { rs_dcerpc_udp_get_current_tx_id(state) });
+        for seqnum in 0..2 {
+            let request = DCERPCUdpHeaderBuilder::new().flags1(PFCL1_LASTFRAG).seqnum(seqnum).build();
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        }
+        let state = &mut dcerpcudp_state as *mut _ as *mut std::os::raw::c_void;
+        assert_eq!(1, unsafe { rs_dcerpc_udp_get_current_tx_id(state) });
+    }
+
+    #[test]
+    fn test_contradictory_frag_flags() {
+        let events = |flags1: u8, fragnum: u16| {
+            let request = DCERPCUdpHeaderBuilder::new().flags1(flags1).fragnum(fragnum).build();
+            DCERPCUDPState::new().parse_datagram(&request).events
         };
-        return ALPROTO_DCERPC;
+        assert_eq!(vec![DCERPCUDPEvent::ContradictoryFragFlags], events(PFCL1_LASTFRAG, 3));
+        assert_eq!(vec![DCERPCUDPEvent::ContradictoryFragFlags], events(0, 1));
+        assert!(events(PFCL1_LASTFRAG, 0).is_empty());
+        assert!(events(PFCL1_FRAG | PFCL1_LASTFRAG, 3).is_empty());
     }
-    return core::ALPROTO_FAILED;
-}
 
-fn register_pattern_probe() -> i8 {
-    unsafe {
-        if AppLayerProtoDetectPMRegisterPatternCSwPP(core::IPPROTO_UDP, ALPROTO_DCERPC,
-                                                     b"|04 00|\0".as_ptr() as *const std::os::raw::c_char, 2, 0,
-                                                     Direction::ToServer.into(), rs_dcerpc_probe_udp, 0, 0) < 0 {
-            SCLogDebug!("TOSERVER => AppLayerProtoDetectPMRegisterPatternCSwPP FAILED");
-            return -1;
+    #[test]
+    fn test_parse_header_pure() {
+        let dcerpcudp_state = DCERPCUDPState::new();
+        let before = format!("{:?}", dcerpcudp_state);
+        let header = parse_header(DCOM_REQUEST).unwrap();
+        assert_eq!(0x2c, header.flags1);
+        assert_eq!(Err(HeaderError::Incomplete(40)), parse_header(&DCOM_REQUEST[..40]).map(|_| ()));
+        let mut request = DCOM_REQUEST.to_vec();
+        request[0] = 5;
+        assert_eq!(Err(HeaderError::BadVersion), parse_header(&request).map(|_| ()));
+        assert_eq!(before, format!("{:?}", dcerpcudp_state));
+    }
+
+    #[test]
+    fn test_reorder_tolerance() {
+        let request = DCERPCUdpHeaderBuilder::new().flags1(PFCL1_LASTFRAG).seqnum(1).opnum(4).build();
+        let response = DCERPCUdpHeaderBuilder::new()
+            .pkt_type(DCERPCPktType::Response)
+            .flags1(PFCL1_LASTFRAG)
+            .seqnum(1)
+            .opnum(4)
+            .build();
+
+        for reorder_tolerance in [false, true] {
+            let mut dcerpcudp_state = DCERPCUDPState::new();
+            dcerpcudp_state.reorder_tolerance = reorder_tolerance;
+            let outcome = dcerpcudp_state.parse_datagram(&response);
+            let unrequested = outcome.events.contains(&DCERPCUDPEvent::ResponseWithoutRequest);
+            assert_eq!(!reorder_tolerance, unrequested);
+            assert!(!dcerpcudp_state.transactions[0].req_done);
+            let outcome = dcerpcudp_state.parse_datagram(&request);
+            assert!(outcome.events.is_empty());
+            assert_eq!(1, dcerpcudp_state.transactions.len());
+            let tx = &dcerpcudp_state.transactions[0];
+            assert!(tx.req_done && tx.resp_done);
+            assert_eq!((1, 1), (tx.frag_cnt_ts, tx.frag_cnt_tc));
+            assert_eq!(ConversationState::Complete, tx.conversation_state());
         }
+
+        // the request never comes
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.reorder_tolerance = true;
+        assert!(dcerpcudp_state.parse_datagram(&response).events.is_empty());
+        dcerpcudp_state.handle_eof();
+        assert_eq!(1, dcerpcudp_state.events);
     }
-    0
-}
 
-export_state_data_get!(rs_dcerpc_udp_get_state_data, DCERPCUDPState);
+    #[test]
+    fn test_distinct_activity_count() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        for (seqnum, activity) in [(1, 0x11), (2, 0x22), (3, 0x11)] {
+            let request = DCERPCUdpHeaderBuilder::new()
+                .flags1(PFCL1_LASTFRAG)
+                .activity_uuid([activity; 16])
+                .seqnum(seqnum)
+                .build();
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        }
+        let state = &mut dcerpcudp_state as *mut _ as *mut std::os::raw::c_void;
+        assert_eq!(2, unsafe { rs_dcerpc_udp_get_distinct_activity_count(state) });
 
-#[no_mangle]
-pub unsafe extern "C" fn rs_dcerpc_udp_register_parser() {
-    let parser = RustParser {
-        name: PARSER_NAME.as_ptr() as *const std::os::raw::c_char,
-        default_port: std::ptr::null(),
-        ipproto: core::IPPROTO_UDP,
-        probe_ts: None,
-        probe_tc: None,
-        min_depth: 0,
-        max_depth: 16,
-        state_new: rs_dcerpc_udp_state_new,
-        state_free: rs_dcerpc_udp_state_free,
-        tx_free: rs_dcerpc_udp_state_transaction_free,
-        parse_ts: rs_dcerpc_udp_parse,
-        parse_tc: rs_dcerpc_udp_parse,
-        get_tx_count: rs_dcerpc_udp_get_tx_cnt,
-        get_tx: rs_dcerpc_udp_get_tx,
-        tx_comp_st_ts: 1,
-        tx_comp_st_tc: 1,
-        tx_get_progress: rs_dcerpc_get_alstate_progress,
-        get_eventinfo: None,
-        get_eventinfo_byid: None,
-        localstorage_new: None,
-        localstorage_free: None,
-        get_tx_files: None,
-        get_tx_iterator: Some(applayer::state_get_tx_iterator::<DCERPCUDPState, DCERPCTransaction>),
-        get_tx_data: rs_dcerpc_udp_get_tx_data,
-        get_state_data: rs_dcerpc_udp_get_state_data,
-        apply_tx_config: None,
-        flags: 0,
-        truncate: None,
-        get_frame_id_by_name: None,
-        get_frame_name_by_id: None,
-    };
+        // the set doesn't grow past its limit
+        for n in 2..DCERPC_UDP_MAX_ACTIVITIES as u32 {
+            let mut uuid = [0xff; 16];
+            uuid[..4].copy_from_slice(&n.to_be_bytes());
+            dcerpcudp_state.activity_uuids.insert(DCERPCUuid(uuid));
+        }
+        let request = DCERPCUdpHeaderBuilder::new()
+            .flags1(PFCL1_LASTFRAG)
+            .activity_uuid([0x33; 16])
+            .seqnum(4)
+            .build();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        assert!(!dcerpcudp_state.activity_uuids.contains(&DCERPCUuid([0x33; 16])));
+        assert_eq!(DCERPC_UDP_MAX_ACTIVITIES, dcerpcudp_state.activity_uuids.len());
+    }
 
-    let ip_proto_str = CString::new("udp").unwrap();
-    if AppLayerProtoDetectConfProtoDetectionEnabled(ip_proto_str.as_ptr(), parser.name) != 0 {
-        let alproto = AppLayerRegisterProtocolDetection(&parser, 1);
-        ALPROTO_DCERPC = alproto;
-        if register_pattern_probe() < 0 {
-            return;
+    #[test]
+    fn test_auth_len_bounds_stub() {
+        let mut request = DCERPCUdpHeaderBuilder::new()
+            .auth_proto(0x09)
+            .fraglen(24)
+            .build();
+        request.extend_from_slice(&[0x41; 24]);
+        request.extend_from_slice(&[0xee; 28]);
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&request)
+        );
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(&[0x41; 24], tx.stub_data_buffer_ts.as_slice());
+        assert_eq!(28, rs_dcerpc_udp_get_tx_auth_len(tx));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "fragments announced")]
+    fn test_debug_check_stub_len() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        // Lose track of the announced length the way a bad decrement of the
+        // remaining fragment length would.
+        dcerpcudp_state.transactions[0].udp_mut().stub_data_expected_len_ts -= 1;
+        let hdr = parse_header(DCOM_REQUEST).unwrap();
+        dcerpcudp_state.debug_check_stub_len(0, DCERPCPktType::Request, &hdr, &DCOM_REQUEST[80..]);
+    }
+
+    #[test]
+    fn test_match_iface_opnum() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        let tx = &mut dcerpcudp_state.transactions[0];
+        let iface = tx.udp().interfaceuuid.0;
+        let opnum = tx.opnum;
+        assert_eq!(1, unsafe { rs_dcerpc_udp_match_iface_opnum(tx, iface.as_ptr(), opnum) });
+        assert_eq!(0, unsafe { rs_dcerpc_udp_match_iface_opnum(tx, iface.as_ptr(), opnum + 1) });
+        let other = [0u8; 16];
+        assert_eq!(0, unsafe { rs_dcerpc_udp_match_iface_opnum(tx, other.as_ptr(), opnum) });
+        assert_eq!(0, unsafe { rs_dcerpc_udp_match_iface_opnum(tx, std::ptr::null(), opnum) });
+    }
+
+    #[test]
+    fn test_progress_completion_status() {
+        let request = DCERPCUdpHeaderBuilder::new().build();
+        let response = DCERPCUdpHeaderBuilder::new()
+            .pkt_type(DCERPCPktType::Response)
+            .build();
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&request)
+        );
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&response)
+        );
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        let tx = &mut dcerpcudp_state.transactions[0] as *mut _ as *mut std::os::raw::c_void;
+        for direction in [Direction::ToServer, Direction::ToClient] {
+            assert_eq!(
+                rs_dcerpc_udp_get_alstate_progress_completion_status(direction.into()),
+                unsafe { rs_dcerpc_get_alstate_progress(tx, direction.into()) }
+            );
         }
-        if AppLayerParserConfParserEnabled(ip_proto_str.as_ptr(), parser.name) != 0 {
-            let _ = AppLayerRegisterParser(&parser, alproto);
+    }
+
+    #[test]
+    fn test_fack_acked_frags() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        for n in [0, 1, 3] {
+            assert_eq!(
+                AppLayerResult::ok(),
+                dcerpcudp_state.handle_input_data(&dcom_fragment(n))
+            );
         }
-    } else {
-        SCLogDebug!("Protocol detecter and parser disabled for DCERPC/UDP.");
+        // fragments 0 and 1 in order, 3 selectively
+        let mut fack = DCOM_REQUEST[..80].to_vec();
+        fack[1] = DCERPC_TYPE_FACK;
+        fack[2] = 0;
+        fack[74..76].copy_from_slice(&20u16.to_le_bytes());
+        fack[76..78].copy_from_slice(&1u16.to_le_bytes());
+        fack.extend_from_slice(&[0x00, 0x00, 0x00, 0x01, 0x00, 0x10, 0x00, 0x00,
+                                 0xb4, 0x05, 0x00, 0x00, 0x03, 0x00, 0x01, 0x00,
+                                 0x02, 0x00, 0x00, 0x00]);
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(&fack)
+        );
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        let tx = &mut dcerpcudp_state.transactions[0];
+        let mut buf: *const u16 = std::ptr::null();
+        let mut len = 0;
+        assert_eq!(1, unsafe { rs_dcerpc_udp_get_tx_acked_frags(tx, &mut buf, &mut len) });
+        let acked = unsafe { std::slice::from_raw_parts(buf, len as usize) };
+        assert_eq!(&[0, 1, 3], acked);
+    }
+
+    #[test]
+    fn test_tx_snapshot() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(
+            AppLayerResult::ok(),
+            dcerpcudp_state.handle_input_data(DCOM_REQUEST)
+        );
+        let tx = &mut dcerpcudp_state.transactions[0];
+        let snapshot = tx.snapshot();
+        assert_eq!(tx.id, snapshot.id);
+        assert_eq!(tx.seqnum, snapshot.seqnum);
+        assert_eq!(tx.opnum, snapshot.opnum);
+        assert_eq!(tx.activityuuid, snapshot.activityuuid);
+        assert_eq!(tx.udp().interfaceuuid, snapshot.interfaceuuid);
+        assert_eq!(tx.req_done, snapshot.req_done);
+        assert_eq!(tx.frag_cnt_ts, snapshot.frag_cnt_ts);
+        assert_eq!(tx.stub_data_buffer_ts, snapshot.stub_data_ts);
+        assert_eq!(&DCOM_REQUEST[80..], snapshot.stub_data_ts.as_slice());
+        let mut c_snapshot = DCERPCUDPTxSnapshot::default();
+        assert_eq!(1, unsafe { rs_dcerpc_udp_get_tx_snapshot(tx, &mut c_snapshot) });
+        assert_eq!(tx.seqnum, c_snapshot.seqnum);
+        assert_eq!(tx.opnum, c_snapshot.opnum);
+        assert_eq!(tx.activityuuid.0, c_snapshot.activityuuid);
+        assert_eq!(tx.udp().interfaceuuid.0, c_snapshot.interfaceuuid);
+        assert_eq!(1392, c_snapshot.stub_data_len_ts);
+        assert_eq!(0, c_snapshot.stub_data_len_tc);
+        // the snapshot outlives the state
+        drop(dcerpcudp_state);
+        assert_eq!(1392, snapshot.stub_data_ts.len());
     }
-}
 
+    #[test]
+    fn test_unexpected_stub_data() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(DCOM_REQUEST));
+        let mut ping = DCOM_REQUEST[..80].to_vec();
+        ping[1] = DCERPC_TYPE_PING;
+        ping[74..76].copy_from_slice(&0u16.to_le_bytes());
+        dcerpcudp_state.parse_datagram(&ping);
+        assert_eq!(0, dcerpcudp_state.events);
 
-#[cfg(test)]
-mod tests {
-    use crate::applayer::AppLayerResult;
-    use crate::dcerpc::dcerpc_udp::DCERPCUDPState;
-    use crate::dcerpc::parser;
+        ping[74..76].copy_from_slice(&4u16.to_le_bytes());
+        ping.extend_from_slice(b"data");
+        let outcome = dcerpcudp_state.parse_datagram(&ping);
+        assert_eq!(vec![DCERPCUDPEvent::UnexpectedStubData], outcome.events);
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        assert_eq!(1, dcerpcudp_state.events);
+    }
 
     #[test]
-    fn test_process_header_udp_incomplete_hdr() {
-        let request: &[u8] = &[
-            0x04, 0x00, 0x08, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xb8, 0x4a, 0x9f, 0x4d,
-            0x1c, 0x7d, 0xcf, 0x11,
-        ];
+    fn test_flow_stub_budget() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.flow_stub_budget = 3 * 1392 + 100;
+        for seqnum in 0..5 {
+            let mut request = DCOM_REQUEST.to_vec();
+            request[64] = seqnum;
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        }
+        assert_eq!(5, dcerpcudp_state.transactions.len());
+        assert_eq!(3 * 1392 + 100, dcerpcudp_state.stub_bytes);
+        for tx in dcerpcudp_state.transactions.range(..3) {
+            assert_eq!(1392, tx.stub_data_buffer_ts.len());
+            assert!(!tx.udp().stub_data_truncated_ts);
+        }
+        assert_eq!(100, dcerpcudp_state.transactions[3].stub_data_buffer_ts.len());
+        assert!(dcerpcudp_state.transactions[3].udp().stub_data_truncated_ts);
+        assert!(dcerpcudp_state.transactions[4].stub_data_buffer_ts.is_empty());
+        assert!(dcerpcudp_state.transactions[4].udp().stub_data_truncated_ts);
+        assert_eq!(2, dcerpcudp_state.events);
 
-        match parser::parse_dcerpc_udp_header(request) {
-            Ok((_rem, _header)) => {
-                { assert!(false); }
-            }
-            _ => {}
+        // freeing a transaction returns its stub data to the budget
+        dcerpcudp_state.free_tx(0);
+        assert_eq!(2 * 1392 + 100, dcerpcudp_state.stub_bytes);
+        let mut request = DCOM_REQUEST.to_vec();
+        request[64] = 5;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        assert_eq!(1392, dcerpcudp_state.transactions[4].stub_data_buffer_ts.len());
+        assert_eq!(2, dcerpcudp_state.events);
+    }
+
+    #[test]
+    fn test_tx_is_authenticated() {
+        let mut request = DCOM_REQUEST.to_vec();
+        request[78] = 0x0a; // auth_proto: NTLMSSP
+        request.extend_from_slice(&[0xaa; 16]);
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(1, rs_dcerpc_udp_get_tx_is_authenticated(tx));
+        assert_eq!(Some("ntlmssp"), auth_proto_name(tx.udp().auth_type));
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(DCOM_REQUEST));
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(0, rs_dcerpc_udp_get_tx_is_authenticated(tx));
+    }
+
+    #[test]
+    fn test_large_datagram() {
+        let mut request = DCOM_REQUEST.to_vec();
+        request.extend_from_slice(&dcom_fragment(1));
+        request.extend_from_slice(&dcom_fragment(2));
+        assert!(request.len() > 4096);
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        assert_eq!(1, dcerpcudp_state.events);
+        assert_eq!(3 * 1392, dcerpcudp_state.transactions[0].stub_data_buffer_ts.len());
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(DCOM_REQUEST));
+        assert_eq!(0, dcerpcudp_state.events);
+    }
+
+    #[test]
+    fn test_metadata_only() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.metadata_only = true;
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(DCOM_REQUEST));
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&dcom_fragment(1)));
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        let (_, hdr) = parser::parse_dcerpc_udp_header(DCOM_REQUEST).unwrap();
+        let tx = &dcerpcudp_state.transactions[0];
+        assert_eq!(hdr.opnum, tx.opnum);
+        assert_eq!(hdr.interfaceuuid, tx.udp().interfaceuuid);
+        assert_eq!(hdr.activityuuid, tx.activityuuid);
+        assert_eq!(2, tx.frag_cnt_ts);
+        assert!(tx.stub_data_buffer_ts.is_empty());
+        assert_eq!(0, dcerpcudp_state.stub_bytes);
+
+        let response = DCERPCUdpHeaderBuilder::new()
+            .pkt_type(DCERPCPktType::Response)
+            .build();
+        let request = DCERPCUdpHeaderBuilder::new().build();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&response));
+        assert_eq!(2, dcerpcudp_state.transactions.len());
+        let tx = &dcerpcudp_state.transactions[1];
+        assert!(tx.req_done && tx.resp_done);
+    }
+
+    #[test]
+    fn test_serial_interface_conflict() {
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        let request = DCERPCUdpHeaderBuilder::new()
+            .interface_uuid([0x11; 16])
+            .serial(7)
+            .seqnum(1)
+            .build();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        // same interface, no conflict
+        let request = DCERPCUdpHeaderBuilder::new()
+            .interface_uuid([0x11; 16])
+            .serial(7)
+            .seqnum(2)
+            .build();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        assert_eq!(0, dcerpcudp_state.events);
+
+        let request = DCERPCUdpHeaderBuilder::new()
+            .interface_uuid([0x22; 16])
+            .serial(7)
+            .seqnum(3)
+            .build();
+        let outcome = dcerpcudp_state.parse_datagram(&request);
+        assert_eq!(vec![DCERPCUDPEvent::SerialInterfaceConflict], outcome.events);
+        assert_eq!(3, dcerpcudp_state.transactions.len());
+
+        // the same serial in another activity is another call
+        let request = DCERPCUdpHeaderBuilder::new()
+            .interface_uuid([0x22; 16])
+            .activity_uuid([0x33; 16])
+            .serial(7)
+            .build();
+        assert!(dcerpcudp_state.parse_datagram(&request).events.is_empty());
+    }
+
+    #[test]
+    fn test_reassembly_completeness() {
+        let mut last = dcom_fragment(3);
+        last[2] |= PFCL1_LASTFRAG;
+
+        // fragments 0 and 3 of a four fragment request
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(DCOM_REQUEST));
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&last));
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert!((tx.reassembly_completeness() - 0.5).abs() < 0.001);
+        assert_eq!(5000, rs_dcerpc_udp_get_tx_reassembly_completeness(tx));
+
+        // all four fragments
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        for fragnum in 0..3 {
+            assert_eq!(
+                AppLayerResult::ok(),
+                dcerpcudp_state.handle_input_data(&dcom_fragment(fragnum))
+            );
         }
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&last));
+        let tx = &mut dcerpcudp_state.transactions[0];
+        assert_eq!(1.0, tx.reassembly_completeness());
+        assert_eq!(10000, rs_dcerpc_udp_get_tx_reassembly_completeness(tx));
+
+        assert_eq!(1.0, DCERPCTransaction::new().reassembly_completeness());
     }
 
     #[test]
-    fn test_process_header_udp_perfect_hdr() {
-        let request: &[u8] = &[
-            0x04, 0x00, 0x08, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xb8, 0x4a, 0x9f, 0x4d,
-            0x1c, 0x7d, 0xcf, 0x11, 0x86, 0x1e, 0x00, 0x20, 0xaf, 0x6e, 0x7c, 0x57, 0x86, 0xc2,
-            0x37, 0x67, 0xf7, 0x1e, 0xd1, 0x11, 0xbc, 0xd9, 0x00, 0x60, 0x97, 0x92, 0xd2, 0x6c,
-            0x79, 0xbe, 0x01, 0x34, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0xff, 0xff, 0xff, 0xff, 0x68, 0x00, 0x00, 0x00, 0x0a, 0x00,
-        ];
-        match parser::parse_dcerpc_udp_header(request) {
-            Ok((rem, header)) => {
-                assert_eq!(4, header.rpc_vers);
-                assert_eq!(80, request.len() - rem.len());
-            }
-            _ => { assert!(false); }
+    fn test_ack() {
+        let request = DCERPCUdpHeaderBuilder::new().flags1(PFCL1_LASTFRAG).seqnum(1).build();
+        let response = DCERPCUdpHeaderBuilder::new()
+            .pkt_type(DCERPCPktType::Response)
+            .flags1(PFCL1_LASTFRAG)
+            .seqnum(1)
+            .build();
+        let ack = DCERPCUdpHeaderBuilder::new().pkt_type(DCERPCPktType::Ack).seqnum(1).build();
+
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&response));
+        assert!(!dcerpcudp_state.transactions[0].udp().client_acked);
+        let outcome = dcerpcudp_state.parse_datagram(&ack);
+        assert!(outcome.complete);
+        assert!(outcome.events.is_empty());
+        assert_eq!(1, dcerpcudp_state.transactions.len());
+        let tx = &dcerpcudp_state.transactions[0];
+        assert!(tx.udp().client_acked);
+        assert!(tx.req_done && tx.resp_done);
+
+        // the acknowledged call is evicted before older complete calls
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        dcerpcudp_state.max_transactions = 2;
+        for seqnum in 0..2 {
+            let request = DCERPCUdpHeaderBuilder::new().flags1(PFCL1_LASTFRAG).seqnum(seqnum).build();
+            let response = DCERPCUdpHeaderBuilder::new()
+                .pkt_type(DCERPCPktType::Response)
+                .flags1(PFCL1_LASTFRAG)
+                .seqnum(seqnum)
+                .build();
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+            assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&response));
         }
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&ack));
+        let request = DCERPCUdpHeaderBuilder::new().flags1(PFCL1_LASTFRAG).seqnum(2).build();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&request));
+        let seqnums: Vec<u32> = dcerpcudp_state.transactions.iter().map(|tx| tx.seqnum).collect();
+        assert_eq!(vec![0, 2], seqnums);
+
+        // without a matching call there is nothing to update
+        let mut dcerpcudp_state = DCERPCUDPState::new();
+        assert_eq!(AppLayerResult::ok(), dcerpcudp_state.handle_input_data(&ack));
+        assert!(dcerpcudp_state.transactions.is_empty());
     }
 
     #[test]
-    fn test_handle_fragment_data_udp_no_body() {
+    fn test_peek_iface_opnum() {
         let request: &[u8] = &[
             0x04, 0x00, 0x08, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xb8, 0x4a, 0x9f, 0x4d,
@@ -447,134 +5690,45 @@ mod tests {
             0x79, 0xbe, 0x01, 0x34, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
             0xff, 0xff, 0xff, 0xff, 0x68, 0x00, 0x00, 0x00, 0x0a, 0x00,
         ];
-        match parser::parse_dcerpc_udp_header(request) {
-            Ok((rem, header)) => {
-                assert_eq!(4, header.rpc_vers);
-                assert_eq!(80, request.len() - rem.len());
-                assert_eq!(0, rem.len());
-            }
-            _ => { assert!(false); }
+        let (iface, opnum) = peek_iface_opnum(request).unwrap();
+        assert_eq!("4d9f4ab8-7d1c-11cf-861e-0020af6e7c57", iface.to_string());
+        assert_eq!(0, opnum);
+
+        // same as the parser, in both byte orders
+        let big_endian = DCERPCUdpHeaderBuilder::new().drep_byte_order(false).opnum(0x0102).build();
+        for input in [DCOM_REQUEST, big_endian.as_slice()] {
+            let header = parse_header(input).unwrap();
+            assert_eq!(Some((header.interfaceuuid, header.opnum)), peek_iface_opnum(input));
         }
+
+        assert_eq!(None, peek_iface_opnum(&request[..79]));
+        let mut request = request.to_vec();
+        request[0] = 5;
+        assert_eq!(None, peek_iface_opnum(&request));
     }
 
     #[test]
-    fn test_handle_input_data_udp_full_body() {
-        let request: &[u8] = &[
-            0x04, 0x00, 0x2c, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xa0, 0x01, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0x3f, 0x98,
-            0xf0, 0x5c, 0xd9, 0x63, 0xcc, 0x46, 0xc2, 0x74, 0x51, 0x6c, 0x8a, 0x53, 0x7d, 0x6f,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00,
-            0xff, 0xff, 0xff, 0xff, 0x70, 0x05, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x06, 0x00,
-            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x32, 0x24, 0x58, 0xfd, 0xcc, 0x45,
-            0x64, 0x49, 0xb0, 0x70, 0xdd, 0xae, 0x74, 0x2c, 0x96, 0xd2, 0x60, 0x5e, 0x0d, 0x00,
-            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x70, 0x5e, 0x0d, 0x00, 0x02, 0x00,
-            0x00, 0x00, 0x7c, 0x5e, 0x0d, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00,
-            0x80, 0x96, 0xf1, 0xf1, 0x2a, 0x4d, 0xce, 0x11, 0xa6, 0x6a, 0x00, 0x20, 0xaf, 0x6e,
-            0x72, 0xf4, 0x0c, 0x00, 0x00, 0x00, 0x4d, 0x41, 0x52, 0x42, 0x01, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x0d, 0xf0, 0xad, 0xba, 0x00, 0x00, 0x00, 0x00, 0xa8, 0xf4,
-            0x0b, 0x00, 0x10, 0x09, 0x00, 0x00, 0x10, 0x09, 0x00, 0x00, 0x4d, 0x45, 0x4f, 0x57,
-            0x04, 0x00, 0x00, 0x00, 0xa2, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0x38, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0x00, 0x00, 0x00, 0x00, 0xe0, 0x08,
-            0x00, 0x00, 0xd8, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x10, 0x08, 0x00,
-            0xcc, 0xcc, 0xcc, 0xcc, 0xc8, 0x00, 0x00, 0x00, 0x4d, 0x45, 0x4f, 0x57, 0xd8, 0x08,
-            0x00, 0x00, 0xd8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
-            0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc4, 0x28, 0xcd, 0x00, 0x64, 0x29, 0xcd, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0xb9, 0x01, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0xab, 0x01, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0xa5, 0x01,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
-            0xa6, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x46, 0xa4, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x46, 0xad, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0xaa, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0x07, 0x00, 0x00, 0x00, 0x60, 0x00,
-            0x00, 0x00, 0x58, 0x00, 0x00, 0x00, 0x90, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00,
-            0x20, 0x00, 0x00, 0x00, 0x28, 0x06, 0x00, 0x00, 0x30, 0x00, 0x00, 0x00, 0x01, 0x00,
-            0x00, 0x00, 0x01, 0x10, 0x08, 0x00, 0xcc, 0xcc, 0xcc, 0xcc, 0x50, 0x00, 0x00, 0x00,
-            0x4f, 0xb6, 0x88, 0x20, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x01, 0x10, 0x08, 0x00, 0xcc, 0xcc, 0xcc, 0xcc, 0x48, 0x00, 0x00, 0x00, 0x07, 0x00,
-            0x66, 0x00, 0x06, 0x09, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x46, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x78, 0x19, 0x0c, 0x00,
-            0x58, 0x00, 0x00, 0x00, 0x05, 0x00, 0x06, 0x00, 0x01, 0x00, 0x00, 0x00, 0x70, 0xd8,
-            0x98, 0x93, 0x98, 0x4f, 0xd2, 0x11, 0xa9, 0x3d, 0xbe, 0x57, 0xb2, 0x00, 0x00, 0x00,
-            0x32, 0x00, 0x31, 0x00, 0x01, 0x10, 0x08, 0x00, 0xcc, 0xcc, 0xcc, 0xcc, 0x80, 0x00,
-            0x00, 0x00, 0x0d, 0xf0, 0xad, 0xba, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x43, 0x14, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x60, 0x00, 0x00, 0x00, 0x60, 0x00, 0x00, 0x00, 0x4d, 0x45, 0x4f, 0x57,
-            0x04, 0x00, 0x00, 0x00, 0xc0, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0x3b, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46, 0x00, 0x00, 0x00, 0x00, 0x30, 0x00,
-            0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x81, 0xc5, 0x17, 0x03, 0x80, 0x0e, 0xe9, 0x4a,
-            0x99, 0x99, 0xf1, 0x8a, 0x50, 0x6f, 0x7a, 0x85, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x10, 0x08, 0x00, 0xcc, 0xcc,
-            0xcc, 0xcc, 0x30, 0x00, 0x00, 0x00, 0x78, 0x00, 0x6e, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0xd8, 0xda, 0x0d, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x2f,
-            0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x46, 0x00, 0x58, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x01, 0x10, 0x08, 0x00, 0xcc, 0xcc, 0xcc, 0xcc, 0x10, 0x00, 0x00, 0x00,
-            0x30, 0x00, 0x2e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x10, 0x08, 0x00, 0xcc, 0xcc, 0xcc, 0xcc,
-            0x68, 0x00, 0x00, 0x00, 0x0e, 0x00, 0xff, 0xff, 0x68, 0x8b, 0x0b, 0x00, 0x02, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfe, 0x02, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0xfe, 0x02, 0x00, 0x00, 0x5c, 0x00, 0x5c, 0x00, 0x31, 0x00,
-            0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x31, 0x00,
-            0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x31, 0x00,
-            0x31, 0x00, 0x31, 0x00, 0x31, 0x00, 0x9d, 0x13, 0x00, 0x01, 0xcc, 0xe0, 0xfd, 0x7f,
-            0xcc, 0xe0, 0xfd, 0x7f, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90, 0x90,
-            0x90, 0x90,
-        ];
+    fn test_parse_udp_input_len_too_large() {
         let mut dcerpcudp_state = DCERPCUDPState::new();
+        let state = &mut dcerpcudp_state as *mut DCERPCUDPState as *mut std::os::raw::c_void;
+        let input = unsafe {
+            StreamSlice::from_raw_parts(DCOM_REQUEST.as_ptr(), u32::MAX, core::STREAM_TOSERVER, 0)
+        };
         assert_eq!(
-            AppLayerResult::ok(),
-            dcerpcudp_state.handle_input_data(request)
+            AppLayerResult::err(),
+            unsafe { rs_dcerpc_udp_parse(std::ptr::null(), state, std::ptr::null_mut(), input, std::ptr::null()) }
         );
+        assert!(dcerpcudp_state.transactions.is_empty());
+
+        let input = unsafe {
+            StreamSlice::from_raw_parts(
+                DCOM_REQUEST.as_ptr(), DCOM_REQUEST.len() as u32, core::STREAM_TOSERVER, 0,
+            )
+        };
         assert_eq!(
-            1392,
-            dcerpcudp_state.transactions[0].stub_data_buffer_ts.len()
+            AppLayerResult::ok(),
+            unsafe { rs_dcerpc_udp_parse(std::ptr::null(), state, std::ptr::null_mut(), input, std::ptr::null()) }
         );
+        assert_eq!(1, dcerpcudp_state.transactions.len());
     }
 }
@@ -78,7 +78,7 @@ fn match_backuuid(
             }
 
             for i in 0..16 {
-                if if_data.if_uuid[i] != uuidentry.uuid[i] {
+                if if_data.if_uuid[i] != uuidentry.uuid.as_bytes()[i] {
                     SCLogDebug!("Iface UUID and BINDACK Accepted UUID does not match");
                     ret = 0;
                     break;
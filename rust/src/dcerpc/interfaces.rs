@@ -0,0 +1,192 @@
+/* Copyright (C) 2023 Open Information Security Foundation
+ *
+ * You can copy, redistribute or modify this Program under the terms of
+ * the GNU General Public License version 2 as published by the Free
+ * Software Foundation.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * version 2 along with this program; if not, write to the Free Software
+ * Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA
+ * 02110-1301, USA.
+ */
+
+//! Well-known DCERPC interfaces and the names of their operations.
+
+pub struct DCERPCInterface {
+    /// Interface UUID, in the byte order used by the parser.
+    pub uuid: [u8; 16],
+    pub name: &'static str,
+    /// Operation names, indexed by opnum.
+    pub operations: &'static [&'static str],
+}
+
+pub static DCERPC_INTERFACES: &[DCERPCInterface] = &[
+    // e1af8308-5d1f-11c9-91a4-08002b14a0fa
+    DCERPCInterface {
+        uuid: [
+            0xe1, 0xaf, 0x83, 0x08, 0x5d, 0x1f, 0x11, 0xc9, 0x91, 0xa4, 0x08, 0x00, 0x2b, 0x14,
+            0xa0, 0xfa,
+        ],
+        name: "epmapper",
+        operations: &[
+            "ept_insert",
+            "ept_delete",
+            "ept_lookup",
+            "ept_map",
+            "ept_lookup_handle_free",
+            "ept_inq_object",
+            "ept_mgmt_delete",
+        ],
+    },
+    // 99fcfec4-5260-101b-bbcb-00aa0021347a
+    DCERPCInterface {
+        uuid: [
+            0x99, 0xfc, 0xfe, 0xc4, 0x52, 0x60, 0x10, 0x1b, 0xbb, 0xcb, 0x00, 0xaa, 0x00, 0x21,
+            0x34, 0x7a,
+        ],
+        name: "IObjectExporter",
+        operations: &[
+            "ResolveOxid",
+            "SimplePing",
+            "ComplexPing",
+            "ServerAlive",
+            "ResolveOxid2",
+            "ServerAlive2",
+        ],
+    },
+    // 000001a0-0000-0000-c000-000000000046
+    DCERPCInterface {
+        uuid: [
+            0x00, 0x00, 0x01, 0xa0, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x46,
+        ],
+        name: "ISystemActivator",
+        operations: &[
+            "QueryInterface",
+            "AddRef",
+            "Release",
+            "RemoteGetClassObject",
+            "RemoteCreateInstance",
+        ],
+    },
+    // 367abb81-9844-35f1-ad32-98f038001003
+    DCERPCInterface {
+        uuid: [
+            0x36, 0x7a, 0xbb, 0x81, 0x98, 0x44, 0x35, 0xf1, 0xad, 0x32, 0x98, 0xf0, 0x38, 0x00,
+            0x10, 0x03,
+        ],
+        name: "svcctl",
+        operations: &[
+            "RCloseServiceHandle",
+            "RControlService",
+            "RDeleteService",
+            "RLockServiceDatabase",
+            "RQueryServiceObjectSecurity",
+            "RSetServiceObjectSecurity",
+            "RQueryServiceStatus",
+            "RSetServiceStatus",
+            "RUnlockServiceDatabase",
+            "RNotifyBootConfigStatus",
+            "RI_ScSetServiceBitsW",
+            "RChangeServiceConfigW",
+            "RCreateServiceW",
+            "REnumDependentServicesW",
+            "REnumServicesStatusW",
+            "ROpenSCManagerW",
+            "ROpenServiceW",
+            "RQueryServiceConfigW",
+            "RQueryServiceLockStatusW",
+            "RStartServiceW",
+            "RGetServiceDisplayNameW",
+            "RGetServiceKeyNameW",
+        ],
+    },
+    // e3514235-4b06-11d1-ab04-00c04fc2dcd2
+    DCERPCInterface {
+        uuid: [
+            0xe3, 0x51, 0x42, 0x35, 0x4b, 0x06, 0x11, 0xd1, 0xab, 0x04, 0x00, 0xc0, 0x4f, 0xc2,
+            0xdc, 0xd2,
+        ],
+        name: "drsuapi",
+        operations: &[
+            "IDL_DRSBind",
+            "IDL_DRSUnbind",
+            "IDL_DRSReplicaSync",
+            "IDL_DRSGetNCChanges",
+            "IDL_DRSUpdateRefs",
+            "IDL_DRSReplicaAdd",
+            "IDL_DRSReplicaDel",
+            "IDL_DRSReplicaModify",
+            "IDL_DRSVerifyNames",
+            "IDL_DRSGetMemberships",
+            "IDL_DRSInterDomainMove",
+            "IDL_DRSGetNT4ChangeLog",
+            "IDL_DRSCrackNames",
+            "IDL_DRSWriteSPN",
+            "IDL_DRSRemoveDsServer",
+            "IDL_DRSRemoveDsDomain",
+            "IDL_DRSDomainControllerInfo",
+            "IDL_DRSAddEntry",
+            "IDL_DRSExecuteKCC",
+            "IDL_DRSGetReplInfo",
+            "IDL_DRSAddSidHistory",
+            "IDL_DRSGetMemberships2",
+            "IDL_DRSReplicaVerifyObjects",
+            "IDL_DRSGetObjectExistence",
+            "IDL_DRSQuerySitesByCost",
+        ],
+    },
+];
+
+/// Look up a well-known interface by UUID.
+pub fn lookup_interface(iface_uuid: &[u8]) -> Option<&'static DCERPCInterface> {
+    DCERPC_INTERFACES.iter().find(|iface| iface.uuid == iface_uuid)
+}
+
+/// Resolve the name of operation `opnum` of the interface `iface_uuid`.
+pub fn resolve_operation(iface_uuid: &[u8], opnum: u16) -> Option<&'static str> {
+    lookup_interface(iface_uuid)?.operations.get(opnum as usize).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn uuid(s: &str) -> Vec<u8> {
+        Uuid::parse_str(s).unwrap().as_bytes().to_vec()
+    }
+
+    #[test]
+    fn test_resolve_operation() {
+        assert_eq!(
+            Some("RemoteCreateInstance"),
+            resolve_operation(&uuid("000001a0-0000-0000-c000-000000000046"), 4)
+        );
+        assert_eq!(
+            Some("ServerAlive2"),
+            resolve_operation(&uuid("99fcfec4-5260-101b-bbcb-00aa0021347a"), 5)
+        );
+        assert_eq!(
+            Some("RCreateServiceW"),
+            resolve_operation(&uuid("367abb81-9844-35f1-ad32-98f038001003"), 12)
+        );
+        assert_eq!(
+            Some("IDL_DRSGetNCChanges"),
+            resolve_operation(&uuid("e3514235-4b06-11d1-ab04-00c04fc2dcd2"), 3)
+        );
+        assert_eq!(
+            Some("ept_map"),
+            resolve_operation(&uuid("e1af8308-5d1f-11c9-91a4-08002b14a0fa"), 3)
+        );
+        // unknown opnum and unknown interface
+        assert_eq!(None, resolve_operation(&uuid("e1af8308-5d1f-11c9-91a4-08002b14a0fa"), 7));
+        assert_eq!(None, resolve_operation(&uuid("12345678-1234-1234-1234-123456789abc"), 0));
+        assert_eq!(None, resolve_operation(&[], 0));
+    }
+}
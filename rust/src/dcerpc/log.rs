@@ -14,8 +14,7 @@
  * Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA
  * 02110-1301, USA.
  */
-use uuid::Uuid;
-
+use crate::core::Direction;
 use crate::dcerpc::dcerpc::*;
 use crate::dcerpc::dcerpc_udp::*;
 use crate::jsonbuilder::{JsonBuilder, JsonError};
@@ -38,9 +37,7 @@ fn log_dcerpc_header_tcp(
                     jsb.open_array("interfaces")?;
                     for uuid in &bind.uuid_list {
                         jsb.start_object()?;
-                        let ifstr = Uuid::from_slice(uuid.uuid.as_slice());
-                        let ifstr = ifstr.map(|uuid| uuid.to_hyphenated().to_string()).unwrap();
-                        jsb.set_string("uuid", &ifstr)?;
+                        jsb.set_string("uuid", &uuid.uuid.to_string())?;
                         let vstr = format!("{}.{}", uuid.version, uuid.versionminor);
                         jsb.set_string("version", &vstr)?;
                         jsb.set_uint("ack_result", uuid.result as u64)?;
@@ -81,6 +78,16 @@ fn log_dcerpc_header_tcp(
     return Ok(());
 }
 
+fn log_stub_preview(
+    jsb: &mut JsonBuilder, tx: &DCERPCTransaction, direction: Direction,
+) -> Result<(), JsonError> {
+    let preview = tx.stub_preview(direction, DCERPC_UDP_STUB_PREVIEW_LEN);
+    if !preview.is_empty() {
+        jsb.set_hex("stub_preview", preview)?;
+    }
+    Ok(())
+}
+
 fn log_dcerpc_header_udp(
     jsb: &mut JsonBuilder, _state: &DCERPCUDPState, tx: &DCERPCTransaction,
 ) -> Result<(), JsonError> {
@@ -91,8 +98,14 @@ fn log_dcerpc_header_udp(
             DCERPC_TYPE_REQUEST => {
                 jsb.open_object("req")?;
                 jsb.set_uint("opnum", tx.opnum as u64)?;
+                if let Some(operation) = tx.get_operation_name() {
+                    jsb.set_string("operation", operation)?;
+                }
+                let (vers_major, vers_minor) = tx.get_iface_version();
+                jsb.set_string("interface_version", &format!("{}.{}", vers_major, vers_minor))?;
                 jsb.set_uint("frag_cnt", tx.frag_cnt_ts as u64)?;
                 jsb.set_uint("stub_data_size", tx.stub_data_buffer_ts.len() as u64)?;
+                log_stub_preview(jsb, tx, Direction::ToServer)?;
                 jsb.close()?;
             }
             _ => {}
@@ -109,6 +122,7 @@ fn log_dcerpc_header_udp(
                 jsb.open_object("res")?;
                 jsb.set_uint("frag_cnt", tx.frag_cnt_tc as u64)?;
                 jsb.set_uint("stub_data_size", tx.stub_data_buffer_tc.len() as u64)?;
+                log_stub_preview(jsb, tx, Direction::ToClient)?;
                 jsb.close()?;
             }
             _ => {} // replicating behavior from smb
@@ -116,10 +130,14 @@ fn log_dcerpc_header_udp(
     } else {
         jsb.set_string("response", "UNREPLIED")?;
     }
-    let activityuuid = Uuid::from_slice(tx.activityuuid.as_slice());
-    let activityuuid = activityuuid.map(|uuid| uuid.to_hyphenated().to_string()).unwrap();
-    jsb.set_string("activityuuid", &activityuuid)?;
+    jsb.set_string("activityuuid", &tx.activityuuid.to_string())?;
     jsb.set_uint("seqnum", tx.seqnum as u64)?;
+    if let Some(pkt_type) = DCERPCPktType::from_u8(tx.udp().pkt_type) {
+        jsb.set_string("packet_type", pkt_type.as_str())?;
+    }
+    if tx.udp().auth_type != 0 {
+        jsb.set_string("auth_proto", auth_proto_name(tx.udp().auth_type).unwrap_or("unknown"))?;
+    }
     jsb.set_string("rpc_version", "4.0")?;
     return Ok(());
 }
@@ -137,3 +155,37 @@ pub extern "C" fn rs_dcerpc_log_json_record_udp(
 ) -> bool {
     log_dcerpc_header_udp(jsb, state, tx).is_ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::dcerpc::dcerpc::*;
+    use crate::dcerpc::dcerpc_udp::*;
+    use crate::dcerpc::log::rs_dcerpc_log_json_record_udp;
+    use crate::jsonbuilder::{jb_len, jb_ptr, JsonBuilder};
+
+    #[test]
+    fn test_log_udp_packet_type() {
+        let state = DCERPCUDPState::new();
+        let mut tx = DCERPCTransaction::new();
+        tx.udp_mut().pkt_type = DCERPC_TYPE_REQUEST;
+        let mut jsb = JsonBuilder::try_new_object().unwrap();
+        assert!(rs_dcerpc_log_json_record_udp(&state, &tx, &mut jsb));
+        jsb.close().unwrap();
+        let json = unsafe { std::slice::from_raw_parts(jb_ptr(&mut jsb), jb_len(&jsb)) };
+        let json = std::str::from_utf8(json).unwrap();
+        assert!(json.contains(r#""packet_type":"request""#));
+    }
+
+    #[test]
+    fn test_log_udp_auth_proto() {
+        let state = DCERPCUDPState::new();
+        let mut tx = DCERPCTransaction::new();
+        tx.udp_mut().auth_type = 0x0a;
+        let mut jsb = JsonBuilder::try_new_object().unwrap();
+        assert!(rs_dcerpc_log_json_record_udp(&state, &tx, &mut jsb));
+        jsb.close().unwrap();
+        let json = unsafe { std::slice::from_raw_parts(jb_ptr(&mut jsb), jb_len(&jsb)) };
+        let json = std::str::from_utf8(json).unwrap();
+        assert!(json.contains(r#""auth_proto":"ntlmssp""#));
+    }
+}
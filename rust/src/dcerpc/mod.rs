@@ -19,4 +19,5 @@ pub mod dcerpc;
 pub mod dcerpc_udp;
 pub mod parser;
 pub mod detect;
+pub mod interfaces;
 pub mod log;
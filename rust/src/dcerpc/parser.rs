@@ -15,15 +15,17 @@
  * 02110-1301, USA.
  */
 use crate::dcerpc::dcerpc::{
-    BindCtxItem, DCERPCBind, DCERPCBindAck, DCERPCBindAckResult, DCERPCHdr, DCERPCRequest, Uuid,
+    BindCtxItem, DCERPCBind, DCERPCBindAck, DCERPCBindAckResult, DCERPCHdr, DCERPCRequest,
+    DCERPCUuid, Uuid,
 };
-use crate::dcerpc::dcerpc_udp::DCERPCHdrUdp;
+use crate::dcerpc::dcerpc_udp::{DCERPCHdrUdp, NdrPrefix};
 use nom7::bytes::streaming::take;
 use nom7::combinator::cond;
+use nom7::error::{make_error, ErrorKind};
 use nom7::number::complete::{le_u16, le_u32, le_u8, u16, u32};
 use nom7::number::Endianness;
 use nom7::multi::count;
-use nom7::IResult;
+use nom7::{Err, IResult};
 
 fn uuid_to_vec(uuid: Uuid) -> Vec<u8> {
     let mut uuidtmp = uuid;
@@ -71,6 +73,17 @@ pub fn parse_uuid(i: &[u8]) -> IResult<&[u8], Uuid> {
     Ok((i, uuid))
 }
 
+/// Parse a connectionless UUID. The first three fields are in little endian
+/// order whatever the data representation of the PDU.
+fn parse_udp_uuid(i: &[u8]) -> IResult<&[u8], DCERPCUuid> {
+    let (i, wire) = take(16_usize)(i)?;
+    let mut uuid = DCERPCUuid::from_bytes(wire).unwrap_or_default();
+    uuid.0[0..4].reverse();
+    uuid.0[4..6].reverse();
+    uuid.0[6..8].reverse();
+    Ok((i, uuid))
+}
+
 pub fn parse_dcerpc_udp_header(i: &[u8]) -> IResult<&[u8], DCERPCHdrUdp> {
     let (i, rpc_vers) = le_u8(i)?;
     let (i, pkt_type) = le_u8(i)?;
@@ -79,9 +92,9 @@ pub fn parse_dcerpc_udp_header(i: &[u8]) -> IResult<&[u8], DCERPCHdrUdp> {
     let (i, drep) = take(3_usize)(i)?;
     let endianness = if drep[0] == 0 { Endianness::Big } else { Endianness::Little };
     let (i, serial_hi) = le_u8(i)?;
-    let (i, objectuuid) = take(16_usize)(i)?;
-    let (i, interfaceuuid) = take(16_usize)(i)?;
-    let (i, activityuuid) = take(16_usize)(i)?;
+    let (i, objectuuid) = parse_udp_uuid(i)?;
+    let (i, interfaceuuid) = parse_udp_uuid(i)?;
+    let (i, activityuuid) = parse_udp_uuid(i)?;
     let (i, server_boot) = u32(endianness)(i)?;
     let (i, if_vers) = u32(endianness)(i)?;
     let (i, seqnum) = u32(endianness)(i)?;
@@ -99,27 +112,9 @@ pub fn parse_dcerpc_udp_header(i: &[u8]) -> IResult<&[u8], DCERPCHdrUdp> {
         flags2,
         drep: drep.to_vec(),
         serial_hi,
-        objectuuid: match parse_uuid(objectuuid) {
-            Ok((_, vect)) => assemble_uuid(vect),
-            Err(_e) => {
-                SCLogDebug!("{}", _e);
-                vec![0]
-            },
-        },
-        interfaceuuid: match parse_uuid(interfaceuuid) {
-            Ok((_, vect)) => assemble_uuid(vect),
-            Err(_e) => {
-                SCLogDebug!("{}", _e);
-                vec![0]
-            },
-        },
-        activityuuid: match parse_uuid(activityuuid){
-            Ok((_, vect)) => assemble_uuid(vect),
-            Err(_e) => {
-                SCLogDebug!("{}", _e);
-                vec![0]
-            },
-        },
+        objectuuid,
+        interfaceuuid,
+        activityuuid,
         server_boot,
         if_vers,
         seqnum,
@@ -134,6 +129,130 @@ pub fn parse_dcerpc_udp_header(i: &[u8]) -> IResult<&[u8], DCERPCHdrUdp> {
     Ok((i, header))
 }
 
+/// Parse the conformance and, if present and consistent with it, the
+/// variance that prefix an NDR conformant (varying) array.
+pub fn parse_ndr_prefix(i: &[u8], endianness: Endianness) -> IResult<&[u8], NdrPrefix> {
+    let (i, max_count) = u32(endianness)(i)?;
+    let variance: IResult<&[u8], (u32, u32)> = (|i| {
+        let (i, offset) = u32(endianness)(i)?;
+        let (i, actual_count) = u32(endianness)(i)?;
+        Ok((i, (offset, actual_count)))
+    })(i);
+    match variance {
+        Ok((rem, (offset, actual_count)))
+            if offset as u64 + actual_count as u64 <= max_count as u64 =>
+        {
+            let prefix = NdrPrefix {
+                max_count,
+                offset: Some(offset),
+                actual_count: Some(actual_count),
+            };
+            Ok((rem, prefix))
+        }
+        _ => {
+            let prefix = NdrPrefix {
+                max_count,
+                offset: None,
+                actual_count: None,
+            };
+            Ok((i, prefix))
+        }
+    }
+}
+
+/// Parse the status code from the body of a connectionless fault PDU.
+pub fn parse_fault_status(i: &[u8], endianness: Endianness) -> IResult<&[u8], u32> {
+    u32(endianness)(i)
+}
+
+/// Parse the body of a connectionless fack PDU. Returns the serial number
+/// of the fragment that caused the ack and the selective ack bit masks.
+pub fn parse_fack_body(i: &[u8], endianness: Endianness) -> IResult<&[u8], (u16, Vec<u32>)> {
+    let (i, _vers) = le_u8(i)?;
+    let (i, _pad) = le_u8(i)?;
+    let (i, _window_size) = u16(endianness)(i)?;
+    let (i, _max_tsdu) = u32(endianness)(i)?;
+    let (i, _max_frag_size) = u32(endianness)(i)?;
+    let (i, serial_num) = u16(endianness)(i)?;
+    let (i, selack_len) = u16(endianness)(i)?;
+    let (i, selack) = count(u32(endianness), selack_len as usize)(i)?;
+    Ok((i, (serial_num, selack)))
+}
+
+/// Skip the padding that aligns `i` to 4 bytes from the start of `stub`.
+fn ndr_align4<'a>(stub: &[u8], i: &'a [u8]) -> IResult<&'a [u8], &'a [u8]> {
+    let offset = stub.len() - i.len();
+    take((4 - offset % 4) % 4)(i)
+}
+
+/// Parse the deferred `twr_t` towers referenced by non-null pointers.
+fn parse_epm_towers<'a>(
+    stub: &[u8], mut i: &'a [u8], referents: &[u32], endianness: Endianness,
+) -> IResult<&'a [u8], Vec<&'a [u8]>> {
+    let mut towers = Vec::new();
+    for _ in referents.iter().filter(|&&r| r != 0) {
+        let (rem, _) = ndr_align4(stub, i)?;
+        let (rem, _max_count) = u32(endianness)(rem)?;
+        let (rem, tower_length) = u32(endianness)(rem)?;
+        let (rem, tower) = take(tower_length as usize)(rem)?;
+        towers.push(tower);
+        i = rem;
+    }
+    Ok((i, towers))
+}
+
+/// Parse the towers from the response stub of an endpoint mapper
+/// `ept_lookup` (opnum 2) or `ept_map` (opnum 3) call.
+pub fn parse_epm_response(
+    stub: &[u8], opnum: u16, endianness: Endianness,
+) -> IResult<&[u8], Vec<&[u8]>> {
+    let (i, _entry_handle) = take(20_usize)(stub)?;
+    let (i, _num) = u32(endianness)(i)?;
+    let (i, _max_count) = u32(endianness)(i)?;
+    let (i, _offset) = u32(endianness)(i)?;
+    let (mut i, actual_count) = u32(endianness)(i)?;
+    if actual_count as usize > i.len() / 4 {
+        return Err(Err::Error(make_error(i, ErrorKind::Count)));
+    }
+    let mut referents = Vec::with_capacity(actual_count as usize);
+    for _ in 0..actual_count {
+        if opnum == 2 {
+            // ept_entry_t: object UUID, tower pointer and annotation string
+            let (rem, _object) = take(16_usize)(i)?;
+            let (rem, referent) = u32(endianness)(rem)?;
+            let (rem, _offset) = u32(endianness)(rem)?;
+            let (rem, annotation_len) = u32(endianness)(rem)?;
+            let (rem, _annotation) = take(annotation_len as usize)(rem)?;
+            let (rem, _) = ndr_align4(stub, rem)?;
+            referents.push(referent);
+            i = rem;
+        } else {
+            let (rem, referent) = u32(endianness)(i)?;
+            referents.push(referent);
+            i = rem;
+        }
+    }
+    parse_epm_towers(stub, i, &referents, endianness)
+}
+
+/// Left and right hand side data of a protocol tower floor.
+pub type TowerFloor<'a> = (&'a [u8], &'a [u8]);
+
+/// Parse the floors of a protocol tower. Towers are always little endian.
+pub fn parse_epm_tower_floors(i: &[u8]) -> IResult<&[u8], Vec<TowerFloor<'_>>> {
+    let (mut i, floor_count) = le_u16(i)?;
+    let mut floors = Vec::new();
+    for _ in 0..floor_count {
+        let (rem, lhs_len) = le_u16(i)?;
+        let (rem, lhs) = take(lhs_len as usize)(rem)?;
+        let (rem, rhs_len) = le_u16(rem)?;
+        let (rem, rhs) = take(rhs_len as usize)(rem)?;
+        floors.push((lhs, rhs));
+        i = rem;
+    }
+    Ok((i, floors))
+}
+
 pub fn parse_dcerpc_bindack_result(i: &[u8]) -> IResult<&[u8], DCERPCBindAckResult> {
     let (i, ack_result) = le_u16(i)?;
     let (i,  ack_reason) = le_u16(i)?;
@@ -300,7 +419,7 @@ mod tests {
         assert_eq!(0x00, header.flags2);
         assert_eq!(vec!(0x10, 0x00, 0x00), header.drep);
         assert_eq!(0x00, header.serial_hi);
-        assert_eq!(expected_activityuuid, header.activityuuid);
+        assert_eq!(expected_activityuuid, header.activityuuid.as_bytes());
         assert_eq!(0x3401be79, header.server_boot);
         assert_eq!(0x00000000, header.seqnum);
         assert_eq!(0xffff, header.ihint);
@@ -343,4 +462,37 @@ mod tests {
         assert_eq!(1, ctxitem.version);
         assert_eq!(3, ctxitem.versionminor);
     }
+
+    #[test]
+    fn test_parse_ndr_prefix() {
+        // conformant varying array of 16 elements, 10 transmitted from 2
+        let stub = [
+            0x10, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x41,
+        ];
+        let (rem, prefix) = parse_ndr_prefix(&stub, Endianness::Little).unwrap();
+        assert_eq!(16, prefix.max_count);
+        assert_eq!(Some(2), prefix.offset);
+        assert_eq!(Some(10), prefix.actual_count);
+        assert_eq!(&[0x41], rem);
+
+        // big endian
+        let (_, prefix) = parse_ndr_prefix(&stub, Endianness::Big).unwrap();
+        assert_eq!(0x1000_0000, prefix.max_count);
+        assert_eq!(Some(0x0a00_0000), prefix.actual_count);
+
+        // the counts don't describe a variance, only the conformance is kept
+        let stub = [
+            0x04, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00,
+        ];
+        let (rem, prefix) = parse_ndr_prefix(&stub, Endianness::Little).unwrap();
+        assert_eq!(4, prefix.max_count);
+        assert_eq!(None, prefix.offset);
+        assert_eq!(None, prefix.actual_count);
+        assert_eq!(&stub[4..], rem);
+        let (_, prefix) = parse_ndr_prefix(&stub[..6], Endianness::Little).unwrap();
+        assert_eq!(4, prefix.max_count);
+        assert_eq!(None, prefix.offset);
+
+        assert!(parse_ndr_prefix(&stub[..3], Endianness::Little).is_err());
+    }
 }